@@ -0,0 +1,322 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use ugui_r_rs::controls::control::Control::{self, Label, Stack};
+use ugui_r_rs::controls::control::{BaseControl, Orientation};
+use ugui_r_rs::core::geo::{FlowDirection, Rect};
+use ugui_r_rs::testing::layout::FixedAdvanceMeasurer;
+use ugui_r_rs::theme::Theme;
+
+const DEPTH: usize = 200;
+const ITERATIONS: u32 = 200;
+
+/// The depths the uncached layout is compared at, as it can't lay out the deep tree in any reasonable time
+const BASELINE_DEPTHS: [usize; 3] = [4, 8, 12];
+
+/// The layout as it was before measurements were cached, kept as a baseline
+///
+/// Every control cloned itself and each of its children, and measured its whole subtree again whenever it was placed.
+/// Stacks also laid their children out twice, once inside the stack's bounds and again inside each child's slot, so the
+/// work doubles with every level of nesting. It lays out trees of its own, as only stacks and labels are handled, which
+/// is all the benchmark's trees hold.
+mod uncached {
+    use ugui_r_rs::controls::control::{BaseControl, Orientation};
+    use ugui_r_rs::core::geo::{Alignment, Point, Rect};
+    use ugui_r_rs::render::TextMeasurer;
+    use ugui_r_rs::text;
+    use ugui_r_rs::theme::Theme;
+
+    /// A control the baseline can lay out, which keeps its children itself rather than in its base
+    #[derive(Clone)]
+    pub enum Node {
+        Label {
+            base: BaseControl,
+            text: String,
+        },
+        Stack {
+            base: BaseControl,
+            orientation: Orientation,
+            children: Vec<Node>,
+        },
+    }
+    use Node::{Label, Stack};
+
+    impl Node {
+        pub fn label(id: Option<&str>, text: String) -> Node {
+            Label {
+                base: BaseControl {
+                    id: id.map(str::to_string),
+                    ..Default::default()
+                },
+                text,
+            }
+        }
+
+        pub fn stack(orientation: Orientation, children: Vec<Node>) -> Node {
+            Stack {
+                base: BaseControl::default(),
+                orientation,
+                children,
+            }
+        }
+
+        pub fn base(&self) -> &BaseControl {
+            match self {
+                Label { base, .. } | Stack { base, .. } => base,
+            }
+        }
+
+        fn base_mut(&mut self) -> &mut BaseControl {
+            match self {
+                Label { base, .. } | Stack { base, .. } => base,
+            }
+        }
+    }
+
+    fn desired_size(
+        control: &Node,
+        available: Point,
+        measurer: &mut dyn TextMeasurer,
+        theme: &Theme,
+    ) -> Point {
+        match control {
+            Label { base, text } => {
+                let font_key = base.text_style.font_key(theme);
+                let line_height = base
+                    .text_style
+                    .line_height
+                    .unwrap_or_else(|| measurer.line_spacing(&font_key));
+                text::layout_text(
+                    text,
+                    Point {
+                        x: available.x,
+                        y: f32::INFINITY,
+                    },
+                    base.text_style.wrapping,
+                    base.text_style.trimming,
+                    line_height,
+                    |x| text::measure(x, &font_key, measurer).x,
+                )
+                .size()
+            }
+            Stack {
+                orientation,
+                children,
+                ..
+            } => {
+                let sizes = children
+                    .iter()
+                    .map(|x| desired_size(x, available, measurer, theme))
+                    .collect::<Vec<Point>>();
+                let sum = |f: fn(&Point) -> f32| sizes.iter().map(f).sum::<f32>();
+                let max = |f: fn(&Point) -> f32| sizes.iter().map(f).fold(0.0, f32::max);
+                match orientation {
+                    Orientation::Horizontal => Point {
+                        x: sum(|x| x.x),
+                        y: max(|x| x.y),
+                    },
+                    Orientation::Vertical => Point {
+                        x: max(|x| x.x),
+                        y: sum(|x| x.y),
+                    },
+                }
+            }
+        }
+    }
+
+    fn layout_bounds(
+        control: &Node,
+        parent_rect: Rect,
+        measurer: &mut dyn TextMeasurer,
+        theme: &Theme,
+    ) -> Rect {
+        let base = control.base();
+        let size = desired_size(
+            control,
+            Point {
+                x: parent_rect.w,
+                y: parent_rect.h,
+            },
+            measurer,
+            theme,
+        );
+        let place = |start: f32, length: f32, size: f32, align: Alignment| match align {
+            Alignment::Start => (start, size),
+            Alignment::Center => (start + length / 2.0 - size / 2.0, size),
+            Alignment::End => (start + length - size, size),
+            Alignment::Fill => (start, length),
+        };
+        let (x, w) = place(parent_rect.x, parent_rect.w, size.x, base.h_align);
+        let (y, h) = place(parent_rect.y, parent_rect.h, size.y, base.v_align);
+        Rect { x, y, w, h }
+    }
+
+    pub fn do_layout(
+        control: &mut Node,
+        parent_rect: Rect,
+        measurer: &mut dyn TextMeasurer,
+        theme: &Theme,
+    ) {
+        let cloned = control.clone();
+        control.base_mut().computed_bounds = layout_bounds(&cloned, parent_rect, measurer, theme);
+        let bounds = control.base().computed_bounds;
+        let Stack {
+            orientation,
+            children,
+            ..
+        } = control
+        else {
+            return;
+        };
+        for child in children.iter_mut() {
+            do_layout(child, bounds, measurer, theme);
+        }
+
+        let available = Point {
+            x: bounds.w,
+            y: bounds.h,
+        };
+        let mut offset = 0.0;
+        for child in children {
+            let clone = child.clone();
+            let size = desired_size(&clone, available, measurer, theme);
+            let fit_rect = match orientation {
+                Orientation::Horizontal => Rect::new(bounds.x + offset, bounds.y, size.x, bounds.h),
+                Orientation::Vertical => Rect::new(bounds.x, bounds.y + offset, bounds.w, size.y),
+            };
+            child.base_mut().computed_bounds = layout_bounds(&clone, fit_rect, measurer, theme);
+            do_layout(child, fit_rect, measurer, theme);
+            offset += match orientation {
+                Orientation::Horizontal => size.x,
+                Orientation::Vertical => size.y,
+            };
+        }
+    }
+}
+
+/// Nests stacks `depth` levels deep, each holding a label next to the next level
+///
+/// # Arguments
+///
+/// * `depth`: The number of nested stacks
+/// * `label`: Makes a label from its id and text
+/// * `stack`: Makes a stack from its orientation and children
+fn nest<T>(
+    depth: usize,
+    label: impl Fn(Option<&str>, String) -> T,
+    stack: impl Fn(Orientation, Vec<T>) -> T,
+) -> T {
+    (0..depth)
+        .rev()
+        .fold(label(Some("leaf"), "Leaf".to_string()), |inner, level| {
+            let orientation = if level % 2 == 0 {
+                Orientation::Vertical
+            } else {
+                Orientation::Horizontal
+            };
+            stack(
+                orientation,
+                vec![label(None, format!("Level {}", level)), inner],
+            )
+        })
+}
+
+fn deep_tree(depth: usize) -> Control {
+    nest(
+        depth,
+        |id, text| Label {
+            base: BaseControl {
+                id: id.map(str::to_string),
+                ..Default::default()
+            },
+            text,
+        },
+        |orientation, children| Stack {
+            orientation,
+            base: BaseControl {
+                children,
+                ..Default::default()
+            },
+        },
+    )
+}
+
+/// Times a layout of the tree, after preparing it for each iteration
+fn run(name: &str, content: &mut Control, prepare: impl Fn(&mut Control, u32)) -> Duration {
+    let mut measurer = FixedAdvanceMeasurer::default();
+    let theme = Theme::default();
+    let viewport = Rect::new(0.0, 0.0, 1920.0, 1080.0);
+    content.do_layout(viewport, FlowDirection::default(), &mut measurer, &theme);
+
+    let mut total = Duration::ZERO;
+    for i in 0..ITERATIONS {
+        prepare(content, i);
+        let start = Instant::now();
+        content.do_layout(viewport, FlowDirection::default(), &mut measurer, &theme);
+        total += start.elapsed();
+        black_box(&content);
+    }
+    let average = total / ITERATIONS;
+    println!("{:<32} {:>12.3?} per layout", name, average);
+    average
+}
+
+fn main() {
+    let mut content = deep_tree(DEPTH);
+    println!(
+        "Layout of {} nested stacks, {} iterations each",
+        DEPTH, ITERATIONS
+    );
+
+    let full = run("full (every control dirty)", &mut content, |x, _| {
+        x.invalidate_layout()
+    });
+    let leaf = run("leaf text changed", &mut content, |x, i| {
+        x.modify("leaf", |x| {
+            if let Label { text, .. } = x {
+                *text = format!("Leaf {}", i);
+            }
+        })
+        .unwrap()
+    });
+    let clean = run("nothing changed", &mut content, |_, _| {});
+
+    println!(
+        "Speedup over a full layout: {:.1}x when a leaf changes, {:.1}x when nothing changes",
+        full.as_secs_f64() / leaf.as_secs_f64(),
+        full.as_secs_f64() / clean.as_secs_f64().max(f64::EPSILON)
+    );
+
+    // Both sides lay out every control, so this compares the algorithms rather than the dirty tracking
+    println!();
+    println!(
+        "Full layouts against the uncached baseline, which is too slow to run at the full depth"
+    );
+    for depth in BASELINE_DEPTHS {
+        let mut content = deep_tree(depth);
+        let cached = run(&format!("cached, depth {}", depth), &mut content, |x, _| {
+            x.invalidate_layout()
+        });
+        let baseline = time_baseline(depth);
+        println!(
+            "{:<32} {:>12.3?} per layout, {:.1}x slower",
+            format!("uncached, depth {}", depth),
+            baseline,
+            baseline.as_secs_f64() / cached.as_secs_f64()
+        );
+    }
+}
+
+/// Times a full layout of a tree of the specified depth with the uncached baseline
+fn time_baseline(depth: usize) -> Duration {
+    let mut content = nest(depth, uncached::Node::label, uncached::Node::stack);
+    let mut measurer = FixedAdvanceMeasurer::default();
+    let theme = Theme::default();
+    let viewport = Rect::new(0.0, 0.0, 1920.0, 1080.0);
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        uncached::do_layout(&mut content, viewport, &mut measurer, &theme);
+        black_box(&content);
+    }
+    start.elapsed() / ITERATIONS
+}
@@ -93,7 +93,8 @@ fn main() {
                 ..Default::default()
             },
         })
-        .build();
+        .build()
+        .unwrap();
 
     window.show();
 }
@@ -0,0 +1,82 @@
+use sdl2::event::Event;
+use sdl2::pixels::Color;
+use sdl2::rect::Rect as SdlRect;
+use std::time::Instant;
+use ugui_r_rs::controls::control::Control::{Label, ProgressBar, Stack};
+use ugui_r_rs::controls::control::{BaseControl, Orientation};
+use ugui_r_rs::core::geo::{Alignment, Rect};
+use ugui_r_rs::overlay::OverlayBuilder;
+
+fn main() -> Result<(), String> {
+    // The application owns SDL, the window and the loop, as an emulator front-end would
+    let sdl_context = sdl2::init()?;
+    let window = sdl_context
+        .video()?
+        .window("overlay", 640, 480)
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+    let mut event_pump = sdl_context.event_pump()?;
+
+    let mut overlay = OverlayBuilder::new()
+        .content(Stack {
+            orientation: Orientation::Vertical,
+            base: BaseControl {
+                h_align: Alignment::End,
+                v_align: Alignment::Start,
+                children: vec![
+                    Label {
+                        base: BaseControl {
+                            id: Some("frame".to_string()),
+                            ..Default::default()
+                        },
+                        text: "Frame 0".to_string(),
+                    },
+                    ProgressBar {
+                        base: BaseControl {
+                            h_align: Alignment::Fill,
+                            ..Default::default()
+                        },
+                        minimum: 0.0,
+                        maximum: 1.0,
+                        value: 0.0,
+                        indeterminate: true,
+                        show_percentage: false,
+                        state: Default::default(),
+                    },
+                ],
+                ..Default::default()
+            },
+        })
+        .build(&canvas)?;
+
+    let mut last_frame = Instant::now();
+    for frame in 0u64.. {
+        for event in event_pump.poll_iter() {
+            if let Event::Quit { .. } = event {
+                return Ok(());
+            }
+            overlay.process_event(&event);
+        }
+
+        overlay.content_mut().modify("frame", |x| {
+            if let Label { text, .. } = x {
+                *text = format!("Frame {}", frame);
+            }
+        })?;
+        let now = Instant::now();
+        overlay.update(now - last_frame);
+        last_frame = now;
+        let (width, height) = canvas.output_size()?;
+        overlay.layout(Rect::new(0.0, 0.0, width as f32, height as f32));
+
+        // The application draws its own content first, and the overlay goes on top
+        canvas.set_draw_color(Color::RGB(16, 24, 48));
+        canvas.clear();
+        canvas.set_draw_color(Color::RGB(200, 80, 40));
+        canvas.fill_rect(SdlRect::new((frame % 600) as i32, 220, 40, 40))?;
+        overlay.render(&mut canvas);
+        canvas.present();
+    }
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use crate::core::geo::{Point, Rect, Thickness};
+use crate::render::Renderer;
+use sdl2::pixels::Color;
+
+/// Computes the horizontal extent of a rounded rectangle at the specified vertical position
+fn span(rect: Rect, radius: f32, y: f32) -> Option<(f32, f32)> {
+    if y < rect.y || y >= rect.bottom() {
+        return None;
+    }
+    let radius = radius.min(rect.w / 2.0).min(rect.h / 2.0).max(0.0);
+
+    // Distance into the top or bottom corner region, if the row passes through one
+    let dy = if y < rect.y + radius {
+        rect.y + radius - y
+    } else if y > rect.bottom() - radius {
+        y - (rect.bottom() - radius)
+    } else {
+        0.0
+    };
+    let inset = radius - (radius * radius - dy * dy).max(0.0).sqrt();
+    Some((rect.x + inset, rect.right() - inset))
+}
+
+fn draw_span(renderer: &mut dyn Renderer, y: f32, left: f32, right: f32, color: Color) {
+    if right - left < 0.5 {
+        return;
+    }
+    renderer.draw_line(Point { x: left, y }, Point { x: right - 1.0, y }, color);
+}
+
+/// Fills a rectangle whose corners are rounded by the specified radius
+pub(crate) fn fill_rounded_rect(
+    renderer: &mut dyn Renderer,
+    rect: Rect,
+    radius: f32,
+    color: Color,
+) {
+    if radius <= 0.0 {
+        renderer.fill_rect(rect, color);
+        return;
+    }
+
+    let mut y = rect.y.floor();
+    while y < rect.bottom() {
+        if let Some((left, right)) = span(rect, radius, y + 0.5) {
+            draw_span(renderer, y, left, right, color);
+        }
+        y += 1.0;
+    }
+}
+
+/// Outlines a rectangle whose corners are rounded by the specified radius, with the stroke lying inside the rectangle
+pub(crate) fn stroke_rounded_rect(
+    renderer: &mut dyn Renderer,
+    rect: Rect,
+    radius: f32,
+    thickness: f32,
+    color: Color,
+) {
+    if thickness <= 0.0 {
+        return;
+    }
+
+    let inner = rect.deflate(Thickness::uniform(thickness));
+    let inner_radius = (radius - thickness).max(0.0);
+
+    let mut y = rect.y.floor();
+    while y < rect.bottom() {
+        if let Some((left, right)) = span(rect, radius, y + 0.5) {
+            match span(inner, inner_radius, y + 0.5) {
+                Some((inner_left, inner_right)) => {
+                    draw_span(renderer, y, left, inner_left, color);
+                    draw_span(renderer, y, inner_right, right, color);
+                }
+                None => draw_span(renderer, y, left, right, color),
+            }
+        }
+        y += 1.0;
+    }
+}
+
+/// Gets the total space a border takes up around its children
+pub(crate) fn inset(stroke_thickness: f32, padding: Thickness) -> Thickness {
+    Thickness::new(
+        stroke_thickness + padding.left,
+        stroke_thickness + padding.top,
+        stroke_thickness + padding.right,
+        stroke_thickness + padding.bottom,
+    )
+}
+
+pub(crate) fn render(
+    bounds: Rect,
+    background: Option<Color>,
+    stroke: Option<Color>,
+    stroke_thickness: f32,
+    corner_radius: f32,
+    renderer: &mut dyn Renderer,
+) {
+    if let Some(background) = background {
+        fill_rounded_rect(renderer, bounds, corner_radius, background);
+    }
+    if let Some(stroke) = stroke {
+        stroke_rounded_rect(renderer, bounds, corner_radius, stroke_thickness, stroke);
+    }
+}
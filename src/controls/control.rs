@@ -1,9 +1,20 @@
-use crate::core::geo::{Alignment, Point, Rect};
+use crate::core::geo::{Alignment, FlowDirection, Point, Rect, Thickness};
 use sdl2::pixels::Color;
-use sdl2::render::WindowCanvas;
 
+use crate::controls::data_grid::{DataGridColumn, DataGridState};
+use crate::controls::image::{ImageData, Stretch};
+use crate::controls::progress_bar::ProgressBarState;
+use crate::controls::rich_text::{LinkHandler, RichTextState, TextSpan};
+use crate::controls::tree_view::{TreeViewItem, TreeViewState};
+use crate::controls::{border, data_grid, image, progress_bar, rich_text, tree_view};
 use crate::core::messages::Message;
-use sdl2::ttf::Font;
+use crate::fonts::TextStyle;
+use crate::render::{Renderer, TextMeasurer};
+use crate::skin;
+use crate::text::{self, TextLayout};
+use crate::theme::{Style, Theme, VisualState};
+use std::rc::Rc;
+use std::time::Duration;
 
 /// Describes the flow of a sequence
 #[derive(Clone, PartialEq, Debug, Default)]
@@ -16,8 +27,46 @@ pub enum Orientation {
     Vertical,
 }
 
+/// Describes how many items can be selected at once
+#[derive(Clone, PartialEq, Debug, Default)]
+pub enum SelectionMode {
+    /// At most one item is selected
+    #[default]
+    Single,
+
+    /// Any amount of items can be selected
+    Multiple,
+}
+
+/// Describes what a message changed about a control, from the least to the most work it causes
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(crate) enum Change {
+    /// Nothing the control shows changed
+    None,
+
+    /// The control needs redrawing, but keeps its desired size
+    Appearance,
+
+    /// The control's desired size may have changed, so it needs layout as well as redrawing
+    Layout,
+}
+
+impl Change {
+    /// Gets `Appearance` if `changed` is set, and `None` otherwise
+    pub(crate) fn appearance_if(changed: bool) -> Change {
+        if changed {
+            Change::Appearance
+        } else {
+            Change::None
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct BaseControl {
+    /// The name the control is looked up by, if any
+    pub id: Option<String>,
+
     /// The horizontal alignment relative to the parent
     pub h_align: Alignment,
 
@@ -27,25 +76,82 @@ pub struct BaseControl {
     /// Whether the control is visible
     pub visible: bool,
 
+    /// Whether the control accepts input. Disabled controls don't process messages, and neither do their children
+    pub enabled: bool,
+
     /// The control's children
     pub children: Vec<Control>,
 
     /// A function which is called whenever the control receives a new message
     pub on_message: fn(Message),
 
+    /// The theme used by the control and its children instead of the inherited one
+    pub theme: Option<Rc<Theme>>,
+
+    /// The name of the theme style used instead of the one named after the control's kind
+    pub style: Option<String>,
+
+    /// How the control's text looks, if it displays any
+    pub text_style: TextStyle,
+
+    /// The direction in which the control and its children flow, or `None` to inherit the parent's
+    pub flow_direction: Option<FlowDirection>,
+
+    /// Whether the control and its children are clipped to its bounds when drawn
+    pub clip_to_bounds: bool,
+
     /// The absolute bounds, as computed by the layout engine. (read-only)
     pub computed_bounds: Rect,
+
+    /// The size the control wants, as computed by the last measure. (read-only)
+    pub desired_size: Point,
+
+    /// The space the control was last measured in. (read-only)
+    pub available_size: Point,
+
+    /// The region the control was last arranged in by its parent. (read-only)
+    pub layout_slot: Rect,
+
+    /// The flow direction after inheritance, as computed by the layout engine. (read-only)
+    pub computed_flow_direction: FlowDirection,
+
+    /// The theme the control set for itself when it was last measured. (read-only)
+    pub measured_theme: Option<Rc<Theme>>,
+
+    /// How the control is being interacted with, as tracked by message processing. (read-only)
+    pub visual_state: VisualState,
+
+    /// Whether the control's desired size must be computed again by the next layout. (read-only)
+    pub measure_dirty: bool,
+
+    /// Whether the bounds of the control or any of its descendants must be computed again by the next layout. (read-only)
+    pub arrange_dirty: bool,
 }
 
 impl Default for BaseControl {
     fn default() -> Self {
         BaseControl {
+            id: None,
             h_align: Default::default(),
             v_align: Default::default(),
             children: Default::default(),
             computed_bounds: Default::default(),
+            desired_size: Default::default(),
+            available_size: Default::default(),
+            layout_slot: Default::default(),
+            flow_direction: None,
+            clip_to_bounds: false,
+            computed_flow_direction: Default::default(),
+            measured_theme: None,
             on_message: |x| {},
             visible: true,
+            enabled: true,
+            theme: None,
+            style: None,
+            text_style: Default::default(),
+            visual_state: Default::default(),
+            measure_dirty: true,
+            arrange_dirty: true,
         }
     }
 }
@@ -55,63 +161,510 @@ pub enum Control {
     /// A control which displays text
     Label { base: BaseControl, text: String },
 
+    /// A control which displays text made of differently styled spans
+    ///
+    /// Spans can be parsed from markup via `rich_text::parse_markup`.
+    RichText {
+        base: BaseControl,
+        spans: Vec<TextSpan>,
+        /// A function which is called with a link's target whenever a link span is clicked
+        on_link: LinkHandler,
+        state: RichTextState,
+    },
+
     /// A control which lays out its children in a stack
     Stack {
         base: BaseControl,
         orientation: Orientation,
     },
+
+    /// A control which displays a hierarchy of expandable items
+    TreeView {
+        base: BaseControl,
+        items: Vec<TreeViewItem>,
+        selection_mode: SelectionMode,
+        /// The height of each item row
+        row_height: f32,
+        state: TreeViewState,
+    },
+
+    /// A control which displays rows of tabular data under a header
+    DataGrid {
+        base: BaseControl,
+        columns: Vec<DataGridColumn>,
+        /// The cell values of each row
+        rows: Vec<Vec<String>>,
+        /// The amount of leading columns which stay in place while scrolling horizontally
+        frozen_columns: usize,
+        /// The height of the header and each row
+        row_height: f32,
+        selection_mode: SelectionMode,
+        state: DataGridState,
+    },
+
+    /// A control which indicates the progress of an operation
+    ProgressBar {
+        base: BaseControl,
+        minimum: f32,
+        maximum: f32,
+        value: f32,
+        /// Whether the progress is unknown, in which case an animated indicator is shown instead of the value
+        indeterminate: bool,
+        /// Whether the percentage is shown as text in the center
+        show_percentage: bool,
+        state: ProgressBarState,
+    },
+
+    /// A control which displays a bitmap
+    Image {
+        base: BaseControl,
+        source: ImageData,
+        stretch: Stretch,
+    },
+
+    /// A control which paints a background and outline around its children
+    ///
+    /// The children are laid out inside the area inset by the stroke and padding.
+    /// Unspecified properties are taken from the theme.
+    Border {
+        base: BaseControl,
+        background: Option<Color>,
+        stroke: Option<Color>,
+        stroke_thickness: Option<f32>,
+        corner_radius: Option<f32>,
+        padding: Option<Thickness>,
+    },
 }
 
 impl Control {
-    // TODO: Control templates:
-    //
     // Control enum holds bare minimum controls, and the Control helper functions build hierarchies via predefined templates
 
     /// Generates a button control
-    // pub fn button(base: BaseControl, text: String) {
-    //     Rectangle { something }
-    //     Label { base, text }
-    // }
+    ///
+    /// # Arguments
+    ///
+    /// * `base`: The button's base properties. Its children are replaced by the button's content
+    /// * `text`: The button's text
+    ///
+    /// returns: Control
+    pub fn button(base: BaseControl, text: String) -> Control {
+        Control::Border {
+            base: BaseControl {
+                children: vec![Control::Label {
+                    base: BaseControl {
+                        h_align: Alignment::Center,
+                        v_align: Alignment::Center,
+                        ..Default::default()
+                    },
+                    text,
+                }],
+                style: base.style.or(Some("button".to_string())),
+                ..base
+            },
+            background: None,
+            stroke: None,
+            stroke_thickness: None,
+            corner_radius: None,
+            padding: None,
+        }
+    }
 
     fn get_base_mut(&mut self) -> &mut BaseControl {
         match self {
             Control::Label { base, .. } => base,
+            Control::RichText { base, .. } => base,
             Control::Stack { base, .. } => base,
+            Control::TreeView { base, .. } => base,
+            Control::DataGrid { base, .. } => base,
+            Control::ProgressBar { base, .. } => base,
+            Control::Image { base, .. } => base,
+            Control::Border { base, .. } => base,
             _ => panic!("Expected control, got none"),
         }
     }
-    fn get_base(&self) -> &BaseControl {
+    /// Finds the first control in the tree, in depth-first order, which matches a predicate
+    pub fn find(&self, predicate: &dyn Fn(&Control) -> bool) -> Option<&Control> {
+        if predicate(self) {
+            return Some(self);
+        }
+        self.get_base()
+            .children
+            .iter()
+            .find_map(|x| x.find(predicate))
+    }
+
+    /// Finds every control in the tree which matches a predicate, in depth-first order
+    pub fn find_all(&self, predicate: &dyn Fn(&Control) -> bool) -> Vec<&Control> {
+        let mut found = vec![];
+        self.visit(&mut |x| {
+            if predicate(x) {
+                found.push(x);
+            }
+        });
+        found
+    }
+
+    /// Calls a function for the control and all its descendants, in depth-first order
+    pub fn visit<'a>(&'a self, visitor: &mut dyn FnMut(&'a Control)) {
+        visitor(self);
+        for child in &self.get_base().children {
+            child.visit(visitor);
+        }
+    }
+
+    /// Gets the child indices leading from the control to the first descendant with the specified id
+    fn path_to(&self, id: &str) -> Option<Vec<usize>> {
+        if self.get_base().id.as_deref() == Some(id) {
+            return Some(vec![]);
+        }
+        self.get_base()
+            .children
+            .iter()
+            .enumerate()
+            .find_map(|(i, child)| {
+                child.path_to(id).map(|mut path| {
+                    path.insert(0, i);
+                    path
+                })
+            })
+    }
+
+    /// Ensures an id is used at most once in the tree
+    fn check_unique(&self, id: &str) -> Result<(), String> {
+        let count = self
+            .find_all(&|x| x.get_base().id.as_deref() == Some(id))
+            .len();
+        if count > 1 {
+            return Err(format!("The id `{}` is used by {} controls", id, count));
+        }
+        Ok(())
+    }
+
+    /// Finds the control with the specified id
+    ///
+    /// returns: Result<Option<&Control>, String> An error if more than one control has the id
+    pub fn find_by_id(&self, id: &str) -> Result<Option<&Control>, String> {
+        self.check_unique(id)?;
+        Ok(self.find(&|x| x.get_base().id.as_deref() == Some(id)))
+    }
+
+    /// Finds the control with the specified id, for modification
+    ///
+    /// The control and its ancestors are marked for layout, as the control is expected to change. Its descendants are
+    /// laid out again too if the change affects what they inherit, such as the theme or the flow direction.
+    ///
+    /// returns: Result<Option<&mut Control>, String> An error if more than one control has the id
+    pub fn find_by_id_mut(&mut self, id: &str) -> Result<Option<&mut Control>, String> {
+        self.check_unique(id)?;
+        let Some(path) = self.path_to(id) else {
+            return Ok(None);
+        };
+        Ok(Some(self.descend_invalidating(&path)))
+    }
+
+    /// Follows child indices from the control, marking every control on the way and the last one for measuring
+    fn descend_invalidating(&mut self, path: &[usize]) -> &mut Control {
+        let mut control = self;
+        for &i in path {
+            control.invalidate_measure();
+            control = &mut control.get_base_mut().children[i];
+        }
+        control.invalidate_measure();
+        control
+    }
+
+    /// Finds the control with the specified id for modification, failing if there isn't exactly one
+    fn find_for_change(&mut self, id: &str) -> Result<&mut Control, String> {
+        self.find_by_id_mut(id)?
+            .ok_or_else(|| format!("No control with id `{}`", id))
+    }
+
+    /// Marks the control for measuring by the next layout, which implies arranging it
+    fn invalidate_measure(&mut self) {
+        match self {
+            // A data grid's rows may have been changed along with it
+            Control::DataGrid { state, .. } => state.invalidate_row_order(),
+            // Rich text's spans may have been replaced, so the spans under the mouse are no longer known
+            Control::RichText { state, .. } => {
+                state.hovered_span = None;
+                state.pressed_span = None;
+            }
+            _ => {}
+        }
+        let base = self.get_base_mut();
+        base.measure_dirty = true;
+        base.arrange_dirty = true;
+    }
+
+    /// Marks the control and all its descendants for measuring by the next layout
+    ///
+    /// Needed when something every control depends on changes, such as the theme.
+    pub fn invalidate_layout(&mut self) {
+        self.invalidate_measure();
+        for child in &mut self.get_base_mut().children {
+            child.invalidate_layout();
+        }
+    }
+
+    /// Whether the control or any of its descendants changed in a way which affects layout since the last layout
+    pub fn needs_layout(&self) -> bool {
+        self.get_base().arrange_dirty
+    }
+
+    /// Inserts a child into the control with the specified id
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id`: The id of the control receiving the child
+    /// * `index`: The position among the parent's children, up to their count
+    /// * `child`: The control to insert
+    ///
+    /// returns: Result<(), String> An error if there isn't exactly one control with the id, or if the index is out of range
+    pub fn insert_child(
+        &mut self,
+        parent_id: &str,
+        index: usize,
+        mut child: Control,
+    ) -> Result<(), String> {
+        let children = &mut self.find_for_change(parent_id)?.get_base_mut().children;
+        if index > children.len() {
+            return Err(out_of_range(index, children.len(), parent_id));
+        }
+        child.invalidate_layout();
+        children.insert(index, child);
+        Ok(())
+    }
+
+    /// Removes a child from the control with the specified id
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id`: The id of the control losing the child
+    /// * `index`: The position of the child among the parent's children
+    ///
+    /// returns: Result<Control, String> The removed child, or an error if there isn't exactly one control with the id, or if the index is out of range
+    pub fn remove_child(&mut self, parent_id: &str, index: usize) -> Result<Control, String> {
+        let children = &mut self.find_for_change(parent_id)?.get_base_mut().children;
+        if index >= children.len() {
+            return Err(out_of_range(index, children.len(), parent_id));
+        }
+        Ok(children.remove(index))
+    }
+
+    /// Replaces a child of the control with the specified id
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id`: The id of the control whose child is replaced
+    /// * `index`: The position of the child among the parent's children
+    /// * `child`: The control taking the child's place
+    ///
+    /// returns: Result<Control, String> The replaced child, or an error if there isn't exactly one control with the id, or if the index is out of range
+    pub fn replace_child(
+        &mut self,
+        parent_id: &str,
+        index: usize,
+        mut child: Control,
+    ) -> Result<Control, String> {
+        let children = &mut self.find_for_change(parent_id)?.get_base_mut().children;
+        if index >= children.len() {
+            return Err(out_of_range(index, children.len(), parent_id));
+        }
+        child.invalidate_layout();
+        Ok(std::mem::replace(&mut children[index], child))
+    }
+
+    /// Changes properties of the control with the specified id, and marks it for layout
+    ///
+    /// # Arguments
+    ///
+    /// * `id`: The id of the control to change
+    /// * `change`: A function which changes the control
+    ///
+    /// returns: Result<(), String> An error if there isn't exactly one control with the id
+    pub fn modify(&mut self, id: &str, change: impl FnOnce(&mut Control)) -> Result<(), String> {
+        change(self.find_for_change(id)?);
+        Ok(())
+    }
+
+    /// Gets the ancestors of the control with the specified id, from its parent up to this control
+    ///
+    /// returns: Result<Option<Vec<&Control>>, String> `None` if no control has the id, or an error if more than one does
+    pub fn ancestors_of(&self, id: &str) -> Result<Option<Vec<&Control>>, String> {
+        self.check_unique(id)?;
+        let Some(path) = self.path_to(id) else {
+            return Ok(None);
+        };
+        let mut ancestors = vec![];
+        let mut control = self;
+        for i in path {
+            ancestors.push(control);
+            control = &control.get_base().children[i];
+        }
+        ancestors.reverse();
+        Ok(Some(ancestors))
+    }
+
+    /// Gets the properties shared by all controls, including the bounds computed by the last layout
+    pub fn get_base(&self) -> &BaseControl {
         match self {
             Control::Label { base, .. } => base,
+            Control::RichText { base, .. } => base,
             Control::Stack { base, .. } => base,
+            Control::TreeView { base, .. } => base,
+            Control::DataGrid { base, .. } => base,
+            Control::ProgressBar { base, .. } => base,
+            Control::Image { base, .. } => base,
+            Control::Border { base, .. } => base,
             _ => panic!("Expected control, got none"),
         }
     }
-    fn compute_desired_size<'a>(&self, font: &Font<'a, 'static>) -> Point {
+    /// Gets the theme used by the control, given the one inherited from its parent
+    fn resolve_theme<'t>(&'t self, inherited: &'t Theme) -> &'t Theme {
+        self.get_base().theme.as_deref().unwrap_or(inherited)
+    }
+
+    /// Gets the name of the theme style used by the control
+    fn style_name(&self) -> &str {
+        if let Some(style) = &self.get_base().style {
+            return style;
+        }
+        self.kind()
+    }
+
+    /// Gets the name of the control's variant, which is also its default theme style
+    pub fn kind(&self) -> &'static str {
         match self {
-            Control::Label { base: _, text } => {
-                // Label measurement: string size with current font
-                let size = font.size_of(text).unwrap();
-                Point {
-                    x: size.0 as f32,
-                    y: size.1 as f32,
-                }
+            Control::Label { .. } => "label",
+            Control::RichText { .. } => "rich_text",
+            Control::Stack { .. } => "stack",
+            Control::TreeView { .. } => "tree_view",
+            Control::DataGrid { .. } => "data_grid",
+            Control::ProgressBar { .. } => "progress_bar",
+            Control::Image { .. } => "image",
+            Control::Border { .. } => "border",
+        }
+    }
+
+    /// Gets the control's effective visual state
+    pub fn visual_state(&self) -> VisualState {
+        let base = self.get_base();
+        if !base.enabled {
+            return VisualState::Disabled;
+        }
+        let focused = match self {
+            Control::TreeView { state, .. } => state.focused,
+            Control::DataGrid { state, .. } => state.focused,
+            _ => false,
+        };
+        if focused && base.visual_state == VisualState::Normal {
+            return VisualState::Focused;
+        }
+        base.visual_state
+    }
+
+    /// Gets the style the control is currently painted with
+    fn current_style<'t>(&self, theme: &'t Theme) -> &'t Style {
+        theme.styles(self.style_name()).get(self.visual_state())
+    }
+
+    /// Gets the color the control's text is drawn with
+    fn text_color(&self, theme: &Theme) -> Color {
+        self.get_base()
+            .text_style
+            .color
+            .unwrap_or(self.current_style(theme).foreground)
+    }
+
+    /// Maps a message into the coordinate space of a direction-aware control, which is mirrored when it flows right-to-left
+    fn flow_message(base: &BaseControl, message: &Message) -> Message {
+        match base.computed_flow_direction {
+            FlowDirection::LeftToRight => message.clone(),
+            FlowDirection::RightToLeft => message.mirror(base.computed_bounds),
+        }
+    }
+
+    /// Gets the space a border takes up around its children
+    fn border_inset(
+        stroke_thickness: &Option<f32>,
+        padding: &Option<Thickness>,
+        theme: &Theme,
+    ) -> Thickness {
+        border::inset(
+            stroke_thickness.unwrap_or(theme.metrics.border_thickness),
+            padding.unwrap_or(theme.metrics.padding),
+        )
+    }
+
+    /// Lays out a label's text inside a region of the specified size
+    fn layout_label(
+        base: &BaseControl,
+        text: &str,
+        size: Point,
+        measurer: &mut dyn TextMeasurer,
+        theme: &Theme,
+    ) -> TextLayout {
+        let font_key = base.text_style.font_key(theme);
+        let line_height = base
+            .text_style
+            .line_height
+            .unwrap_or_else(|| measurer.line_spacing(&font_key));
+        text::layout_text(
+            text,
+            size,
+            base.text_style.wrapping,
+            base.text_style.trimming,
+            line_height,
+            |x| text::measure(x, &font_key, measurer).x,
+        )
+    }
+
+    /// Computes the size the control wants, given the sizes its children want
+    fn compute_desired_size(
+        &self,
+        available: Point,
+        children_sizes: &[Point],
+        measurer: &mut dyn TextMeasurer,
+        theme: &Theme,
+    ) -> Point {
+        match self {
+            Control::Label { base, text } => {
+                // Label measurement: size of the text's lines, wrapped to the available width
+                // The height isn't constrained, so no lines are dropped while measuring
+                Control::layout_label(
+                    base,
+                    text,
+                    Point {
+                        x: available.x,
+                        y: f32::INFINITY,
+                    },
+                    measurer,
+                    theme,
+                )
+                .size()
             }
-            Control::Stack {
-                base, orientation, ..
-            } => {
-                if base.children.len() == 0 {
+            Control::RichText { base, spans, .. } => {
+                rich_text::layout(
+                    spans,
+                    &base.text_style,
+                    available.x,
+                    base.computed_flow_direction,
+                    measurer,
+                    theme,
+                )
+                .size
+            }
+            Control::Stack { orientation, .. } => {
+                if children_sizes.is_empty() {
                     return Point::default();
                 }
 
                 // Stack measurement: sum of w/h component of all children, max of w/h component
-                let children_sizes = base.children.iter().map(|x| x.compute_desired_size(font));
-
                 if *orientation == Orientation::Horizontal {
                     Point {
-                        x: children_sizes.clone().map(|x| x.x).sum(),
+                        x: children_sizes.iter().map(|x| x.x).sum(),
                         y: children_sizes
-                            .clone()
+                            .iter()
                             .max_by(|a, b| a.y.total_cmp(&b.y))
                             .unwrap()
                             .y,
@@ -119,20 +672,94 @@ impl Control {
                 } else {
                     Point {
                         x: children_sizes
-                            .clone()
+                            .iter()
                             .max_by(|a, b| a.x.total_cmp(&b.x))
                             .unwrap()
                             .x,
-                        y: children_sizes.clone().map(|x| x.y).sum(),
+                        y: children_sizes.iter().map(|x| x.y).sum(),
                     }
                 }
             }
-            _ => panic!("Not implemented for {:?}", self),
+            Control::TreeView {
+                base,
+                items,
+                row_height,
+                ..
+            } => {
+                let font_key = base.text_style.font_key(theme);
+                tree_view::desired_size(items, *row_height, |text| {
+                    text::measure(text, &font_key, measurer)
+                })
+            }
+            Control::DataGrid {
+                columns,
+                rows,
+                row_height,
+                ..
+            } => data_grid::desired_size(columns, rows, *row_height, available),
+            Control::ProgressBar {
+                base,
+                indeterminate,
+                show_percentage,
+                ..
+            } => {
+                // The text is measured at its widest, so the control doesn't change size as progress is made
+                let text_size = (*show_percentage && !*indeterminate)
+                    .then(|| text::measure("100%", &base.text_style.font_key(theme), measurer));
+                progress_bar::desired_size(text_size)
+            }
+            Control::Image {
+                source, stretch, ..
+            } => {
+                // Images covering the available space are cropped to it
+                let size = image::stretched_size(source.size(), available, *stretch);
+                match stretch {
+                    Stretch::UniformToFill => Point {
+                        x: size.x.min(available.x),
+                        y: size.y.min(available.y),
+                    },
+                    _ => size,
+                }
+            }
+            Control::Border {
+                stroke_thickness,
+                padding,
+                ..
+            } => {
+                // Border measurement: largest child, plus the stroke and padding
+                let inset = Control::border_inset(stroke_thickness, padding, theme);
+                let content_size = children_sizes.iter().fold(Point::default(), |a, b| Point {
+                    x: a.x.max(b.x),
+                    y: a.y.max(b.y),
+                });
+                Point {
+                    x: content_size.x + inset.horizontal(),
+                    y: content_size.y + inset.vertical(),
+                }
+            }
         }
     }
-    fn get_base_layout_bounds<'a>(&self, parent_rect: Rect, font: &Font<'a, 'static>) -> Rect {
+    /// Computes the control's bounds inside the parent's content area, from its measured size
+    ///
+    /// The horizontal alignment is interpreted in the parent's flow direction.
+    fn get_base_layout_bounds(&self, parent_rect: Rect, flow_direction: FlowDirection) -> Rect {
         let base = self.get_base();
-        let size = self.compute_desired_size(font);
+        let h_align = flow_direction.align(base.h_align);
+        let mut size = base.desired_size;
+
+        if let Control::Image {
+            source,
+            stretch: Stretch::Uniform | Stretch::UniformToFill,
+            ..
+        } = self
+        {
+            // A uniformly stretched image filling one axis keeps its aspect ratio on the other one
+            let width = (h_align == Alignment::Fill).then_some(parent_rect.w);
+            let height = (base.v_align == Alignment::Fill).then_some(parent_rect.h);
+            if width.is_some() || height.is_some() {
+                size = image::constrained_size(source.size(), width, height);
+            }
+        }
 
         let mut base_rect = Rect {
             x: parent_rect.x,
@@ -140,13 +767,13 @@ impl Control {
             w: size.x,
             h: size.y,
         };
-        if base.h_align == Alignment::Center {
+        if h_align == Alignment::Center {
             base_rect.x = parent_rect.x + parent_rect.w / 2.0 - size.x / 2.0;
         }
-        if base.h_align == Alignment::End {
+        if h_align == Alignment::End {
             base_rect.x = parent_rect.x + parent_rect.w - size.x;
         }
-        if base.h_align == Alignment::Fill {
+        if h_align == Alignment::Fill {
             base_rect.w = parent_rect.w;
         }
         if base.v_align == Alignment::Center {
@@ -161,88 +788,553 @@ impl Control {
         base_rect
     }
 
-    pub(crate) fn render(&self, window_canvas: &mut WindowCanvas) {
+    /// Draws the control and its children with the bounds computed by the last layout
+    pub fn render(&self, renderer: &mut dyn Renderer, theme: &Theme) {
         let base = self.get_base();
+        let theme = self.resolve_theme(theme);
+        if base.clip_to_bounds {
+            renderer.push_clip(base.computed_bounds);
+        }
+        let style = self.current_style(theme);
+        let skin_part = theme.skin.as_deref().and_then(|skin| {
+            skin.part(self.style_name(), self.visual_state())
+                .map(|part| (skin, part))
+        });
 
-        let color = match self {
-            Control::Stack { orientation, .. } => {
-                if *orientation == Orientation::Horizontal {
-                    Color::RED
-                } else {
-                    Color::YELLOW
-                }
+        match (self, skin_part) {
+            (
+                Control::Border {
+                    background,
+                    stroke,
+                    stroke_thickness,
+                    corner_radius,
+                    ..
+                },
+                _,
+            ) if background.is_some() || stroke.is_some() || skin_part.is_none() => {
+                border::render(
+                    base.computed_bounds,
+                    background.or(style.background),
+                    stroke.or(style.stroke),
+                    stroke_thickness.unwrap_or(theme.metrics.border_thickness),
+                    corner_radius.unwrap_or(theme.metrics.corner_radius),
+                    renderer,
+                );
             }
-            Control::Label { .. } => Color::WHITE,
-            _ => Color::MAGENTA,
-        };
+            (_, Some((skin, part))) => {
+                skin::draw_part(skin, part, base.computed_bounds, renderer);
+            }
+            _ => {
+                border::render(
+                    base.computed_bounds,
+                    style.background,
+                    style.stroke,
+                    theme.metrics.border_thickness,
+                    0.0,
+                    renderer,
+                );
+            }
+        }
 
-        window_canvas.set_draw_color(color);
-        window_canvas
-            .draw_rect(self.get_base().computed_bounds.inflate(-1.0).to_sdl())
-            .unwrap();
+        let font_key = base.text_style.font_key(theme);
+        let text_color = self.text_color(theme);
+        let flow_direction = base.computed_flow_direction;
+        match self {
+            Control::Label { text, .. } => {
+                let bounds = base.computed_bounds;
+                let layout = Control::layout_label(
+                    base,
+                    text,
+                    Point {
+                        x: bounds.w,
+                        y: bounds.h,
+                    },
+                    renderer,
+                    theme,
+                );
+                // Glyphs are centered vertically inside their line
+                let glyph_offset = (layout.line_height - renderer.height(&font_key)) / 2.0;
+                for (i, line) in layout.lines.iter().enumerate() {
+                    text::draw_text(
+                        &line.text,
+                        Point {
+                            x: bounds.x
+                                + layout.line_offset(
+                                    line,
+                                    bounds.w,
+                                    base.text_style.alignment.resolve(flow_direction),
+                                ),
+                            y: bounds.y + i as f32 * layout.line_height + glyph_offset,
+                        },
+                        &font_key,
+                        text_color,
+                        flow_direction.into(),
+                        renderer,
+                    );
+                }
+            }
+            Control::RichText { spans, state, .. } => {
+                rich_text::render(
+                    base.computed_bounds,
+                    spans,
+                    &base.text_style,
+                    text_color,
+                    flow_direction,
+                    state,
+                    theme,
+                    renderer,
+                );
+            }
+            Control::TreeView {
+                items,
+                row_height,
+                state,
+                ..
+            } => {
+                tree_view::render(
+                    base.computed_bounds,
+                    items,
+                    *row_height,
+                    state,
+                    theme,
+                    style,
+                    &font_key,
+                    text_color,
+                    flow_direction,
+                    renderer,
+                );
+            }
+            Control::DataGrid {
+                columns,
+                rows,
+                frozen_columns,
+                row_height,
+                state,
+                ..
+            } => {
+                data_grid::render(
+                    base.computed_bounds,
+                    columns,
+                    rows,
+                    *frozen_columns,
+                    *row_height,
+                    state,
+                    theme,
+                    &font_key,
+                    text_color,
+                    flow_direction,
+                    renderer,
+                );
+            }
+            Control::ProgressBar {
+                minimum,
+                maximum,
+                value,
+                indeterminate,
+                show_percentage,
+                state,
+                ..
+            } => {
+                progress_bar::render(
+                    base.computed_bounds
+                        .deflate(Thickness::uniform(theme.metrics.border_thickness)),
+                    *minimum,
+                    *maximum,
+                    *value,
+                    *indeterminate,
+                    state,
+                    flow_direction,
+                    theme,
+                    renderer,
+                );
+                if *show_percentage && !*indeterminate {
+                    let text = progress_bar::percentage_text(*minimum, *maximum, *value);
+                    let size = text::measure(&text, &font_key, renderer);
+                    text::draw_text(
+                        &text,
+                        Point {
+                            x: base.computed_bounds.x + (base.computed_bounds.w - size.x) / 2.0,
+                            y: base.computed_bounds.y + (base.computed_bounds.h - size.y) / 2.0,
+                        },
+                        &font_key,
+                        text_color,
+                        flow_direction.into(),
+                        renderer,
+                    );
+                }
+            }
+            Control::Image {
+                source, stretch, ..
+            } => {
+                image::render(base.computed_bounds, source, *stretch, renderer);
+            }
+            _ => {}
+        }
 
         for child in &base.children {
-            child.render(window_canvas);
+            child.render(renderer, theme);
+        }
+        if base.clip_to_bounds {
+            renderer.pop_clip();
         }
     }
-    pub(crate) fn do_layout<'a>(&mut self, parent_rect: Rect, font: &Font<'a, 'static>) {
-        let cloned = self.clone();
+
+    /// Computes the bounds of the control and its children inside a region
+    ///
+    /// The tree is measured, then arranged. Controls which haven't changed since the last layout are skipped,
+    /// along with their children.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_rect`: The region available to the control
+    /// * `flow_direction`: The parent's flow direction, which the control inherits unless it sets its own
+    /// * `measurer`: Measures text with the metrics it will be drawn with
+    /// * `theme`: The theme inherited from the parent
+    pub fn do_layout(
+        &mut self,
+        parent_rect: Rect,
+        flow_direction: FlowDirection,
+        measurer: &mut dyn TextMeasurer,
+        theme: &Theme,
+    ) {
+        self.measure(
+            Point {
+                x: parent_rect.w,
+                y: parent_rect.h,
+            },
+            flow_direction,
+            measurer,
+            theme,
+        );
+        self.arrange(parent_rect, flow_direction, measurer, theme);
+    }
+
+    /// Computes the size the control and its children want inside the available space
+    ///
+    /// The size is kept in `desired_size`, and reused while the control is clean and measured in the same space.
+    ///
+    /// # Arguments
+    ///
+    /// * `available`: The size of the space available to the control
+    /// * `flow_direction`: The parent's flow direction, which the control inherits unless it sets its own
+    /// * `measurer`: Measures text with the metrics it will be drawn with
+    /// * `theme`: The theme inherited from the parent
+    ///
+    /// returns: Point The desired size
+    pub fn measure(
+        &mut self,
+        available: Point,
+        flow_direction: FlowDirection,
+        measurer: &mut dyn TextMeasurer,
+        theme: &Theme,
+    ) -> Point {
         let base = self.get_base_mut();
+        if !base.measure_dirty && base.available_size == available {
+            return base.desired_size;
+        }
+        let flow_direction = base.flow_direction.unwrap_or(flow_direction);
+        // Descendants inherit the flow direction and the theme, so they're measured again if either changed
+        let inherited_changed = flow_direction != base.computed_flow_direction
+            || !same_theme(&base.theme, &base.measured_theme);
+        base.computed_flow_direction = flow_direction;
+        base.measured_theme = base.theme.clone();
+        if inherited_changed {
+            base.children
+                .iter_mut()
+                .for_each(Control::invalidate_layout);
+        }
+        // The theme is shared, so it can be used while the children are measured
+        let own_theme = base.theme.clone();
+        let theme = own_theme.as_deref().unwrap_or(theme);
+
+        // Children are measured against the control's content area
+        let content_available = match self {
+            Control::Border {
+                stroke_thickness,
+                padding,
+                ..
+            } => {
+                let inset = Control::border_inset(stroke_thickness, padding, theme);
+                Point {
+                    x: (available.x - inset.horizontal()).max(0.0),
+                    y: (available.y - inset.vertical()).max(0.0),
+                }
+            }
+            _ => available,
+        };
+        let children_sizes = self
+            .get_base_mut()
+            .children
+            .iter_mut()
+            .map(|x| x.measure(content_available, flow_direction, measurer, theme))
+            .collect::<Vec<Point>>();
+        let desired_size = self.compute_desired_size(available, &children_sizes, measurer, theme);
 
-        // Compute the base layout bounds, and apply them
-        base.computed_bounds = cloned.get_base_layout_bounds(parent_rect, font);
+        // The rows or sort may have changed since the grid was last measured, so it's shown in the current order
+        if let Control::DataGrid { rows, state, .. } = self {
+            data_grid::update_row_order(rows, state);
+        }
 
-        for child in &mut base.children {
-            child.do_layout(base.computed_bounds, font);
+        // A new size may move the control inside its slot, so it's arranged again
+        let base = self.get_base_mut();
+        base.desired_size = desired_size;
+        base.available_size = available;
+        base.measure_dirty = false;
+        base.arrange_dirty = true;
+        desired_size
+    }
+
+    /// Positions the control and its children inside a slot, using the sizes computed by the last measure
+    ///
+    /// Controls which are clean and arranged in the same slot keep their bounds, along with their children.
+    ///
+    /// # Arguments
+    ///
+    /// * `slot`: The region assigned to the control by its parent
+    /// * `flow_direction`: The parent's flow direction, which the control's alignment is interpreted in
+    /// * `measurer`: Measures text with the metrics it will be drawn with
+    /// * `theme`: The theme inherited from the parent
+    pub fn arrange(
+        &mut self,
+        slot: Rect,
+        flow_direction: FlowDirection,
+        measurer: &mut dyn TextMeasurer,
+        theme: &Theme,
+    ) {
+        if !self.get_base().arrange_dirty && self.get_base().layout_slot == slot {
+            return;
         }
+        let bounds = self.get_base_layout_bounds(slot, flow_direction);
+        let base = self.get_base_mut();
+        base.computed_bounds = bounds;
+        base.layout_slot = slot;
+        base.arrange_dirty = false;
+        let flow_direction = base.computed_flow_direction;
+        let own_theme = base.theme.clone();
+        let theme = own_theme.as_deref().unwrap_or(theme);
 
-        // Control-specific logic: we reposition childrens' bounds after their layout is finished
-        // (this is only reached after all children are laid out)
-        match cloned {
-            Control::Stack { orientation, .. } => {
-                if orientation == Orientation::Horizontal {
-                    // Accumulate width (needed for horizontal stack)
-                    let mut current_width = 0.0;
-                    for child in &mut base.children {
-                        // Recompute layout bounds inside limited region
-                        let clone = child.clone();
-                        let child_base = child.get_base_mut();
-                        let width = clone.compute_desired_size(font).x;
-                        let fit_rect = Rect {
-                            x: base.computed_bounds.x + current_width,
-                            y: base.computed_bounds.y,
-                            w: width,
-                            h: base.computed_bounds.h,
+        match self {
+            Control::Stack { base, orientation } => {
+                // Each child gets a slot as long as its desired size along the stack
+                // Right-to-left horizontal stacks place their first child at the right edge
+                let mut offset = 0.0;
+                for child in &mut base.children {
+                    let size = child.get_base().desired_size;
+                    let fit_rect = if *orientation == Orientation::Horizontal {
+                        let x = match flow_direction {
+                            FlowDirection::LeftToRight => bounds.x + offset,
+                            FlowDirection::RightToLeft => bounds.right() - offset - size.x,
                         };
-                        child_base.computed_bounds = clone.get_base_layout_bounds(fit_rect, font);
-                        child.do_layout(fit_rect, font);
-                        current_width += width;
-                    }
+                        offset += size.x;
+                        Rect {
+                            x,
+                            y: bounds.y,
+                            w: size.x,
+                            h: bounds.h,
+                        }
+                    } else {
+                        let y = bounds.y + offset;
+                        offset += size.y;
+                        Rect {
+                            x: bounds.x,
+                            y,
+                            w: bounds.w,
+                            h: size.y,
+                        }
+                    };
+                    child.arrange(fit_rect, flow_direction, measurer, theme);
+                }
+            }
+            Control::Border {
+                base,
+                stroke_thickness,
+                padding,
+                ..
+            } => {
+                // Children are arranged inside the area inset by the stroke and padding
+                let inset = Control::border_inset(stroke_thickness, padding, theme);
+                let content_bounds = bounds.deflate(match flow_direction {
+                    FlowDirection::LeftToRight => inset,
+                    FlowDirection::RightToLeft => inset.mirror(),
+                });
+                for child in &mut base.children {
+                    child.arrange(content_bounds, flow_direction, measurer, theme);
+                }
+            }
+            _ => {
+                for child in &mut self.get_base_mut().children {
+                    child.arrange(bounds, flow_direction, measurer, theme);
+                }
+            }
+        }
+
+        // Rich text keeps its layout, so spans can be hit-tested while processing messages
+        if let Control::RichText {
+            base, spans, state, ..
+        } = self
+        {
+            state.layout = rich_text::layout(
+                spans,
+                &base.text_style,
+                base.computed_bounds.w,
+                flow_direction,
+                measurer,
+                theme,
+            );
+        }
+    }
+
+    /// Routes a message to the control and its descendants
+    ///
+    /// returns: bool Whether the control or any of its descendants changed how it looks, and so must be drawn again
+    pub(crate) fn process_message(&mut self, message: Message) -> bool {
+        if !self.get_base().enabled {
+            return false;
+        }
+        (self.get_base().on_message)(message.clone());
+
+        // Track hover and press state for styling
+        let base = self.get_base_mut();
+        let previous_visual_state = base.visual_state;
+        base.visual_state = match message {
+            Message::MouseMove(position) if base.visual_state != VisualState::Pressed => {
+                if position.inside(base.computed_bounds) {
+                    VisualState::Hover
                 } else {
-                    // Accumulate height (needed for vertical stack)
-                    let mut current_height = 0.0;
-                    for child in &mut base.children {
-                        // Recompute layout bounds inside limited region
-                        let clone = child.clone();
-                        let child_base = child.get_base_mut();
-                        let height = clone.compute_desired_size(font).y;
-                        let fit_rect = Rect {
-                            x: base.computed_bounds.x,
-                            y: base.computed_bounds.y + current_height,
-                            w: base.computed_bounds.w,
-                            h: height,
-                        };
-                        child_base.computed_bounds = clone.get_base_layout_bounds(fit_rect, font);
-                        child.do_layout(fit_rect, font);
-                        current_height += height;
-                    }
+                    VisualState::Normal
                 }
             }
+            Message::MouseUp(position) => {
+                if position.inside(base.computed_bounds) {
+                    VisualState::Hover
+                } else {
+                    VisualState::Normal
+                }
+            }
+            Message::MouseDown(position, _) if position.inside(base.computed_bounds) => {
+                VisualState::Pressed
+            }
+            _ => base.visual_state,
+        };
+        let mut changed = base.visual_state != previous_visual_state;
+
+        match self {
+            Control::RichText {
+                base,
+                spans,
+                on_link,
+                state,
+            } => {
+                let previous = (state.hovered_span, state.pressed_span);
+                rich_text::process_message(
+                    base.computed_bounds,
+                    spans,
+                    *on_link,
+                    state,
+                    message.clone(),
+                );
+                changed |= (state.hovered_span, state.pressed_span) != previous;
+            }
+            Control::TreeView {
+                base,
+                items,
+                selection_mode,
+                row_height,
+                state,
+            } => {
+                // Expanding or collapsing items changes which rows are shown, and so the desired size
+                let change = tree_view::process_message(
+                    base.computed_bounds,
+                    items,
+                    selection_mode,
+                    *row_height,
+                    state,
+                    Control::flow_message(base, &message),
+                );
+                if change == Change::Layout {
+                    base.measure_dirty = true;
+                    base.arrange_dirty = true;
+                }
+                changed |= change != Change::None;
+            }
+            Control::DataGrid {
+                base,
+                columns,
+                rows,
+                frozen_columns,
+                row_height,
+                selection_mode,
+                state,
+            } => {
+                // Resizing columns changes the desired size
+                let change = data_grid::process_message(
+                    base.computed_bounds,
+                    columns,
+                    rows,
+                    *frozen_columns,
+                    *row_height,
+                    selection_mode,
+                    state,
+                    Control::flow_message(base, &message),
+                );
+                if change == Change::Layout {
+                    base.measure_dirty = true;
+                    base.arrange_dirty = true;
+                }
+                changed |= change != Change::None;
+            }
             _ => {}
         }
+
+        let base = self.get_base_mut();
+        for child in &mut base.children {
+            changed |= child.process_message(message.clone());
+            // A child's desired size contributes to its parent's
+            if child.get_base().measure_dirty {
+                base.measure_dirty = true;
+                base.arrange_dirty = true;
+            }
+        }
+        changed
+    }
+
+    /// Whether the control or any of its descendants is animated, and so needs new frames while nothing else changes
+    pub fn is_animating(&self) -> bool {
+        self.find(&|x| {
+            matches!(x, Control::ProgressBar { base, indeterminate: true, .. } if base.visible)
+        })
+        .is_some()
     }
 
-    pub(crate) fn process_message(&mut self, message: Message) {
-        println!("{:#?}", message);
+    /// Advances time-based state, such as animations, by the elapsed frame time
+    pub(crate) fn update(&mut self, delta: Duration) {
+        if let Control::ProgressBar {
+            indeterminate: true,
+            state,
+            ..
+        } = self
+        {
+            progress_bar::update(state, delta);
+        }
+
+        for child in &mut self.get_base_mut().children {
+            child.update(delta);
+        }
+    }
+}
+
+fn out_of_range(index: usize, count: usize, parent_id: &str) -> String {
+    format!(
+        "Index {} is out of range for the {} children of `{}`",
+        index, count, parent_id
+    )
+}
+
+/// Whether two optional themes are the same shared theme
+fn same_theme(a: &Option<Rc<Theme>>, b: &Option<Rc<Theme>>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
     }
 }
@@ -0,0 +1,682 @@
+use crate::controls::control::{Change, SelectionMode};
+use crate::core::geo::{FlowDirection, Point, Rect};
+use crate::core::messages::Message;
+use crate::fonts::FontKey;
+use crate::render::Renderer;
+use crate::text;
+use crate::theme::{Theme, VisualState};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::pixels::Color;
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+/// The distance from a column's right edge within which dragging resizes the column
+const RESIZE_GRIP_WIDTH: f32 = 4.0;
+
+/// The amount of rows scrolled per mouse wheel step
+const WHEEL_ROWS: f32 = 3.0;
+
+/// Describes the order in which rows are sorted
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortDirection {
+    Ascending,
+    Descending,
+}
+
+/// The horizontal space between a cell's edge and its text
+const CELL_TEXT_MARGIN: f32 = 4.0;
+
+/// Describes how a cell's value is presented
+#[derive(Clone, Debug, Default)]
+pub enum CellTemplate {
+    /// The value is shown as text
+    #[default]
+    Text,
+
+    /// The value is drawn by a custom function, which receives the cell's value and its absolute bounds
+    Custom(fn(&str, Rect, &mut dyn Renderer)),
+}
+
+// Custom templates are considered equal, since function addresses aren't meaningful to compare
+impl PartialEq for CellTemplate {
+    fn eq(&self, other: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+}
+
+/// A column inside a `Control::DataGrid`
+#[derive(Clone, PartialEq, Debug)]
+pub struct DataGridColumn {
+    /// The text shown in the column's header
+    pub header: String,
+
+    /// The column's width
+    pub width: f32,
+
+    /// The smallest width the column can be resized to
+    pub min_width: f32,
+
+    /// Whether clicking the column's header sorts the rows by it
+    pub sortable: bool,
+
+    /// How the column's cells are presented
+    pub template: CellTemplate,
+}
+
+impl DataGridColumn {
+    /// Creates a new sortable text column
+    pub fn new(header: &str, width: f32) -> DataGridColumn {
+        DataGridColumn {
+            header: header.to_string(),
+            width,
+            min_width: 16.0,
+            sortable: true,
+            template: CellTemplate::Text,
+        }
+    }
+}
+
+/// The interaction state of a `Control::DataGrid`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct DataGridState {
+    /// The indices of all selected rows, relative to the unsorted row collection
+    pub selected: BTreeSet<usize>,
+
+    /// The row which shift-selected ranges extend from, relative to the unsorted row collection
+    pub anchor: Option<usize>,
+
+    /// The row which keyboard navigation operates on, relative to the unsorted row collection
+    pub cursor: Option<usize>,
+
+    /// The column the rows are sorted by
+    pub sort: Option<(usize, SortDirection)>,
+
+    /// The scroll offset of the scrollable region
+    pub scroll: Point,
+
+    /// Whether the data grid receives keyboard input
+    pub focused: bool,
+
+    /// Maps display positions to row indices, as produced by sorting
+    row_order: Vec<usize>,
+
+    /// Maps row indices to display positions, the inverse of `row_order`
+    display_indices: Vec<usize>,
+
+    /// Whether `row_order` was built from the current rows, which is cleared when they may have changed
+    row_order_current: bool,
+
+    /// The sort `row_order` was built with
+    row_order_sort: Option<(usize, SortDirection)>,
+
+    /// The last known mouse position
+    mouse_position: Point,
+
+    /// The column being resized, along with the mouse position and column width at the time the drag started
+    resizing: Option<(usize, f32, f32)>,
+
+    /// The column whose header was pressed
+    pressed_header: Option<usize>,
+}
+
+impl DataGridState {
+    /// Gets the row index shown at the specified display position
+    pub fn row_at(&self, display_index: usize) -> usize {
+        self.row_order
+            .get(display_index)
+            .copied()
+            .unwrap_or(display_index)
+    }
+
+    /// Gets the display position of a row, if the row exists
+    fn display_index_of(&self, row: usize) -> Option<usize> {
+        self.display_indices.get(row).copied()
+    }
+
+    /// Marks the display order for rebuilding, as the rows may have changed
+    pub(crate) fn invalidate_row_order(&mut self) {
+        self.row_order_current = false;
+    }
+}
+
+/// Compares two cell values, numerically if both are numbers
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.total_cmp(&b),
+        _ => a.cmp(b),
+    }
+}
+
+/// Rebuilds the display order from the rows and sort settings
+fn sort_rows(rows: &[Vec<String>], state: &mut DataGridState) {
+    state.row_order = (0..rows.len()).collect();
+    state.row_order_current = true;
+    state.row_order_sort = state.sort;
+
+    if let Some((column, direction)) = state.sort {
+        let empty = String::new();
+        state.row_order.sort_by(|a, b| {
+            let ordering = compare_cells(
+                rows[*a].get(column).unwrap_or(&empty),
+                rows[*b].get(column).unwrap_or(&empty),
+            );
+            match direction {
+                SortDirection::Ascending => ordering,
+                SortDirection::Descending => ordering.reverse(),
+            }
+        });
+    }
+
+    state.display_indices = vec![0; rows.len()];
+    for (display_index, row) in state.row_order.iter().enumerate() {
+        state.display_indices[*row] = display_index;
+    }
+}
+
+/// Rebuilds the display order if the rows or the sort changed since it was built
+pub(crate) fn update_row_order(rows: &[Vec<String>], state: &mut DataGridState) {
+    if !state.row_order_current
+        || state.row_order_sort != state.sort
+        || state.row_order.len() != rows.len()
+    {
+        sort_rows(rows, state);
+    }
+}
+
+fn content_size(columns: &[DataGridColumn], row_count: usize, row_height: f32) -> Point {
+    Point {
+        x: columns.iter().map(|x| x.width).sum(),
+        y: (row_count + 1) as f32 * row_height,
+    }
+}
+
+fn clamp_scroll(
+    bounds: Rect,
+    columns: &[DataGridColumn],
+    row_count: usize,
+    row_height: f32,
+    scroll: Point,
+) -> Point {
+    let size = content_size(columns, row_count, row_height);
+    Point {
+        x: scroll.x.clamp(0.0, (size.x - bounds.w).max(0.0)),
+        y: scroll.y.clamp(0.0, (size.y - bounds.h).max(0.0)),
+    }
+}
+
+/// Computes the absolute horizontal extents of each column, accounting for frozen columns and scrolling
+fn column_extents(
+    bounds: Rect,
+    columns: &[DataGridColumn],
+    frozen_columns: usize,
+    scroll: Point,
+) -> Vec<(f32, f32)> {
+    let mut x = bounds.x;
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let offset = if i < frozen_columns { 0.0 } else { scroll.x };
+            let extent = (x - offset, column.width);
+            x += column.width;
+            extent
+        })
+        .collect()
+}
+
+/// Finds the column under the specified x position, giving frozen columns priority
+fn column_at(extents: &[(f32, f32)], frozen_columns: usize, x: f32) -> Option<usize> {
+    let hit = |i: &usize| x >= extents[*i].0 && x < extents[*i].0 + extents[*i].1;
+    (0..frozen_columns.min(extents.len()))
+        .find(hit)
+        .or_else(|| (frozen_columns.min(extents.len())..extents.len()).find(hit))
+}
+
+/// Selects a row according to the selection mode and the held modifiers
+fn select(state: &mut DataGridState, selection_mode: &SelectionMode, row: usize, keymod: Mod) {
+    let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+    let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+
+    let anchor = state.anchor.and_then(|x| state.display_index_of(x));
+    let target = state.display_index_of(row);
+    if *selection_mode == SelectionMode::Multiple && ctrl {
+        if !state.selected.remove(&row) {
+            state.selected.insert(row);
+        }
+        state.anchor = Some(row);
+    } else if let (SelectionMode::Multiple, true, Some(from), Some(to)) =
+        (selection_mode, shift, anchor, target)
+    {
+        // The range replaces the selection, so it shrinks when the row moves back towards the anchor
+        state.selected = (from.min(to)..=from.max(to))
+            .map(|x| state.row_at(x))
+            .collect();
+    } else {
+        state.selected = BTreeSet::from([row]);
+        state.anchor = Some(row);
+    }
+    state.cursor = Some(row);
+}
+
+/// Computes the desired size of a data grid, which fits all columns and rows up to the available space
+///
+/// Content beyond the available space is scrolled to, below the header and beside the frozen columns.
+pub(crate) fn desired_size(
+    columns: &[DataGridColumn],
+    rows: &[Vec<String>],
+    row_height: f32,
+    available: Point,
+) -> Point {
+    let size = content_size(columns, rows.len(), row_height);
+    Point {
+        x: size.x.min(available.x),
+        y: size.y.min(available.y),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+/// Handles a message sent to a data grid
+///
+/// returns: Change `Layout` if a column was resized, and `Appearance` if only the selection, sort, scrolling or focus
+/// changed
+pub(crate) fn process_message(
+    bounds: Rect,
+    columns: &mut [DataGridColumn],
+    rows: &[Vec<String>],
+    frozen_columns: usize,
+    row_height: f32,
+    selection_mode: &SelectionMode,
+    state: &mut DataGridState,
+    message: Message,
+) -> Change {
+    update_row_order(rows, state);
+    state.scroll = clamp_scroll(bounds, columns, rows.len(), row_height, state.scroll);
+    if row_height <= 0.0 {
+        return Change::None;
+    }
+
+    let extents = column_extents(bounds, columns, frozen_columns, state.scroll);
+    let viewport_rows = ((bounds.h - row_height) / row_height).floor().max(1.0) as usize;
+
+    match message {
+        Message::MouseMove(position) => {
+            state.mouse_position = position;
+            let Some((column, start_x, start_width)) = state.resizing else {
+                return Change::None;
+            };
+            let column = &mut columns[column];
+            let width = (start_width + position.x - start_x).max(column.min_width);
+            if column.width == width {
+                return Change::None;
+            }
+            column.width = width;
+            Change::Layout
+        }
+        Message::MouseDown(position, keymod) => {
+            state.mouse_position = position;
+            let focused = position.inside(bounds);
+            let focus_change = Change::appearance_if(state.focused != focused);
+            state.focused = focused;
+            if !state.focused {
+                return focus_change;
+            }
+
+            if position.y < bounds.y + row_height {
+                let Some(column) = column_at(&extents, frozen_columns, position.x) else {
+                    return focus_change;
+                };
+                let (x, width) = extents[column];
+                if x + width - position.x <= RESIZE_GRIP_WIDTH {
+                    state.resizing = Some((column, position.x, columns[column].width));
+                } else if column > 0 && position.x - x <= RESIZE_GRIP_WIDTH {
+                    state.resizing = Some((column - 1, position.x, columns[column - 1].width));
+                } else {
+                    // The pressed header is drawn differently
+                    state.pressed_header = Some(column);
+                    return Change::Appearance;
+                }
+                return focus_change;
+            }
+
+            let display_index =
+                ((position.y - bounds.y - row_height + state.scroll.y) / row_height) as usize;
+            if display_index >= rows.len() {
+                return focus_change;
+            }
+            let row = state.row_at(display_index);
+            select(state, selection_mode, row, keymod);
+            Change::Appearance
+        }
+        Message::MouseUp(position) => {
+            if state.resizing.take().is_some() {
+                return Change::None;
+            }
+            let Some(pressed) = state.pressed_header.take() else {
+                return Change::None;
+            };
+            let released = if position.y >= bounds.y && position.y < bounds.y + row_height {
+                column_at(&extents, frozen_columns, position.x)
+            } else {
+                None
+            };
+            if released != Some(pressed) || !columns[pressed].sortable {
+                return Change::Appearance;
+            }
+
+            state.sort = match state.sort {
+                Some((column, SortDirection::Ascending)) if column == pressed => {
+                    Some((pressed, SortDirection::Descending))
+                }
+                _ => Some((pressed, SortDirection::Ascending)),
+            };
+            sort_rows(rows, state);
+            Change::Appearance
+        }
+        Message::MouseWheel(delta) => {
+            if !state.mouse_position.inside(bounds) {
+                return Change::None;
+            }
+            let scroll = clamp_scroll(
+                bounds,
+                columns,
+                rows.len(),
+                row_height,
+                Point {
+                    x: state.scroll.x + delta.x * row_height * WHEEL_ROWS,
+                    y: state.scroll.y - delta.y * row_height * WHEEL_ROWS,
+                },
+            );
+            let change = Change::appearance_if(state.scroll != scroll);
+            state.scroll = scroll;
+            change
+        }
+        Message::KeyDown(keycode, keymod) => {
+            if !state.focused || rows.is_empty() {
+                return Change::None;
+            }
+
+            let current = state.cursor.and_then(|x| state.display_index_of(x));
+            let last = rows.len() - 1;
+            let target = match (keycode, current) {
+                (Keycode::Up | Keycode::Down | Keycode::PageUp | Keycode::PageDown, None) => 0,
+                (Keycode::Up, Some(i)) => i.saturating_sub(1),
+                (Keycode::Down, Some(i)) => (i + 1).min(last),
+                (Keycode::PageUp, Some(i)) => i.saturating_sub(viewport_rows),
+                (Keycode::PageDown, Some(i)) => (i + viewport_rows).min(last),
+                (Keycode::Home, _) => 0,
+                (Keycode::End, _) => last,
+                _ => return Change::None,
+            };
+            let row = state.row_at(target);
+            select(state, selection_mode, row, keymod);
+
+            // Bring the new cursor row into view
+            let row_top = target as f32 * row_height;
+            let viewport_height = bounds.h - row_height;
+            if row_top < state.scroll.y {
+                state.scroll.y = row_top;
+            } else if row_top + row_height > state.scroll.y + viewport_height {
+                state.scroll.y = row_top + row_height - viewport_height;
+            }
+            state.scroll = clamp_scroll(bounds, columns, rows.len(), row_height, state.scroll);
+            Change::Appearance
+        }
+        Message::TextInput(_) => Change::None,
+    }
+}
+
+/// Draws a string inside a cell, clipped to the cell
+fn draw_cell_text(
+    value: &str,
+    rect: Rect,
+    font: &FontKey,
+    color: Color,
+    flow_direction: FlowDirection,
+    renderer: &mut dyn Renderer,
+) {
+    let visible = renderer.clip().map_or(rect, |x| x.intersect(rect));
+    if visible.w <= 0.0 || visible.h <= 0.0 {
+        return;
+    }
+
+    renderer.push_clip(rect);
+    let size = text::measure(value, font, renderer);
+    text::draw_text(
+        value,
+        Point {
+            x: match flow_direction {
+                FlowDirection::LeftToRight => rect.x + CELL_TEXT_MARGIN,
+                FlowDirection::RightToLeft => rect.right() - CELL_TEXT_MARGIN - size.x,
+            },
+            y: rect.y + (rect.h - size.y) / 2.0,
+        },
+        font,
+        color,
+        flow_direction.into(),
+        renderer,
+    );
+    renderer.pop_clip();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_cell(
+    column: &DataGridColumn,
+    value: &str,
+    rect: Rect,
+    theme: &Theme,
+    font: &FontKey,
+    text_color: Color,
+    flow_direction: FlowDirection,
+    renderer: &mut dyn Renderer,
+) {
+    match column.template {
+        CellTemplate::Text => {
+            draw_cell_text(value, rect, font, text_color, flow_direction, renderer)
+        }
+        CellTemplate::Custom(template) => template(value, rect, renderer),
+    }
+    renderer.stroke_rect(rect, theme.palette.border);
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render(
+    bounds: Rect,
+    columns: &[DataGridColumn],
+    rows: &[Vec<String>],
+    frozen_columns: usize,
+    row_height: f32,
+    state: &DataGridState,
+    theme: &Theme,
+    font: &FontKey,
+    text_color: Color,
+    flow_direction: FlowDirection,
+    renderer: &mut dyn Renderer,
+) {
+    if row_height <= 0.0 {
+        return;
+    }
+
+    // Columns are computed left-to-right, and mirrored when drawn for right-to-left grids
+    let flow = |rect: Rect| match flow_direction {
+        FlowDirection::LeftToRight => rect,
+        FlowDirection::RightToLeft => rect.mirror(bounds),
+    };
+
+    let scroll = clamp_scroll(bounds, columns, rows.len(), row_height, state.scroll);
+    let extents = column_extents(bounds, columns, frozen_columns, scroll);
+    let frozen_right = extents
+        .iter()
+        .take(frozen_columns)
+        .map(|(x, w)| x + w)
+        .fold(bounds.x, f32::max);
+
+    // Only rows intersecting both the control and the canvas are visited, which keeps huge grids cheap
+    let visible_bottom = bounds.bottom().min(renderer.viewport().bottom());
+    let first_row = (scroll.y / row_height).floor() as usize;
+    let visible_rows =
+        ((visible_bottom - bounds.y - row_height).max(0.0) / row_height).ceil() as usize + 1;
+
+    let body = Rect {
+        y: bounds.y + row_height,
+        h: (bounds.h - row_height).max(0.0),
+        ..bounds
+    };
+
+    // Scrollable columns first, then frozen columns on top of them
+    let scrollable = Rect {
+        x: frozen_right,
+        w: (bounds.right() - frozen_right).max(0.0),
+        ..body
+    };
+    let frozen = Rect {
+        w: (frozen_right - bounds.x).max(0.0),
+        ..body
+    };
+    for (region, range) in [
+        (scrollable, frozen_columns.min(columns.len())..columns.len()),
+        (frozen, 0..frozen_columns.min(columns.len())),
+    ] {
+        if region.w <= 0.0 || region.h <= 0.0 {
+            continue;
+        }
+        let region = flow(region);
+        renderer.push_clip(region);
+
+        for display_index in first_row..(first_row + visible_rows).min(rows.len()) {
+            let row = state.row_at(display_index);
+            let y = bounds.y + row_height * (display_index + 1) as f32 - scroll.y;
+
+            if state.selected.contains(&row) {
+                renderer.fill_rect(
+                    Rect {
+                        y,
+                        h: row_height,
+                        ..region
+                    },
+                    theme.palette.selection,
+                );
+            }
+
+            for column in range.clone() {
+                let (x, w) = extents[column];
+                let value = rows[row].get(column).map(String::as_str).unwrap_or("");
+                draw_cell(
+                    &columns[column],
+                    value,
+                    flow(Rect::new(x, y, w, row_height)),
+                    theme,
+                    font,
+                    text_color,
+                    flow_direction,
+                    renderer,
+                );
+            }
+
+            if state.focused && state.cursor == Some(row) {
+                renderer.stroke_rect(
+                    Rect {
+                        y,
+                        h: row_height,
+                        ..body
+                    },
+                    theme.palette.accent,
+                );
+            }
+        }
+        renderer.pop_clip();
+    }
+
+    // The header row stays in place while scrolling vertically
+    let header_styles = theme.styles("data_grid_header");
+    let header = Rect {
+        h: row_height,
+        ..bounds
+    };
+    for (region, range) in [
+        (
+            Rect {
+                x: frozen_right,
+                w: (bounds.right() - frozen_right).max(0.0),
+                ..header
+            },
+            frozen_columns.min(columns.len())..columns.len(),
+        ),
+        (
+            Rect {
+                w: (frozen_right - bounds.x).max(0.0),
+                ..header
+            },
+            0..frozen_columns.min(columns.len()),
+        ),
+    ] {
+        if region.w <= 0.0 {
+            continue;
+        }
+        let region = flow(region);
+        renderer.push_clip(region);
+
+        for column in range {
+            let (x, w) = extents[column];
+            let rect = flow(Rect::new(x, header.y, w, row_height));
+            let header_style = header_styles.get(if state.pressed_header == Some(column) {
+                VisualState::Pressed
+            } else {
+                VisualState::Normal
+            });
+            if let Some(background) = header_style.background {
+                renderer.fill_rect(rect, background);
+            }
+            if let Some(stroke) = header_style.stroke {
+                renderer.stroke_rect(rect, stroke);
+            }
+            draw_cell_text(
+                &columns[column].header,
+                rect,
+                font,
+                header_style.foreground,
+                flow_direction,
+                renderer,
+            );
+
+            // Sort indicator: a small chevron at the header's right side
+            if let Some((sorted, direction)) = state.sort {
+                if sorted == column {
+                    let center = Point {
+                        x: match flow_direction {
+                            FlowDirection::LeftToRight => rect.right() - 8.0,
+                            FlowDirection::RightToLeft => rect.x + 8.0,
+                        },
+                        y: rect.y + row_height / 2.0,
+                    };
+                    let dy = match direction {
+                        SortDirection::Ascending => -2.0,
+                        SortDirection::Descending => 2.0,
+                    };
+                    renderer.draw_line(
+                        Point {
+                            x: center.x - 4.0,
+                            y: center.y - dy,
+                        },
+                        Point {
+                            x: center.x,
+                            y: center.y + dy,
+                        },
+                        header_style.foreground,
+                    );
+                    renderer.draw_line(
+                        Point {
+                            x: center.x,
+                            y: center.y + dy,
+                        },
+                        Point {
+                            x: center.x + 4.0,
+                            y: center.y - dy,
+                        },
+                        header_style.foreground,
+                    );
+                }
+            }
+        }
+        renderer.pop_clip();
+    }
+}
@@ -0,0 +1,281 @@
+use crate::core::geo::{Point, Rect};
+use crate::render::Renderer;
+use sdl2::pixels::PixelFormatEnum;
+use sdl2::render::{BlendMode, Texture, TextureCreator};
+use sdl2::surface::Surface;
+use sdl2::video::WindowContext;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::rc::Rc;
+
+/// Describes how an image is resized to fill its bounds
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Stretch {
+    /// The image keeps its original size
+    None,
+
+    /// The image is resized to fill the bounds, disregarding its aspect ratio
+    Fill,
+
+    /// The image is resized to fit inside the bounds while keeping its aspect ratio
+    #[default]
+    Uniform,
+
+    /// The image is resized to cover the bounds while keeping its aspect ratio, cropping the excess
+    UniformToFill,
+}
+
+/// Decoded RGBA pixel data
+///
+/// Cloning is cheap, as the pixels are shared between clones.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ImageData {
+    pub width: u32,
+    pub height: u32,
+
+    /// The pixels, stored row by row as 4 bytes per pixel in R, G, B, A order
+    pub pixels: Rc<[u8]>,
+}
+
+impl ImageData {
+    /// Creates an image from a raw RGBA buffer
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: The image's width in pixels
+    /// * `height`: The image's height in pixels
+    /// * `pixels`: The pixels, stored row by row as 4 bytes per pixel in R, G, B, A order
+    ///
+    /// returns: Result<ImageData, String> An error if the buffer's length doesn't match the dimensions
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Result<ImageData, String> {
+        if pixels.len() != width as usize * height as usize * 4 {
+            return Err(format!(
+                "Expected {} bytes for a {}x{} image, got {}",
+                width as usize * height as usize * 4,
+                width,
+                height,
+                pixels.len()
+            ));
+        }
+        Ok(ImageData {
+            width,
+            height,
+            pixels: pixels.into(),
+        })
+    }
+
+    /// Loads a BMP file
+    ///
+    /// # Arguments
+    ///
+    /// * `path`: The path to the BMP file
+    ///
+    /// returns: Result<ImageData, String> An error if the file can't be read or decoded
+    pub fn load_bmp<P: AsRef<Path>>(path: P) -> Result<ImageData, String> {
+        ImageData::from_surface(&Surface::load_bmp(path)?)
+    }
+
+    /// Copies the pixels of an SDL surface, converting them to RGBA if necessary
+    pub fn from_surface(surface: &Surface) -> Result<ImageData, String> {
+        let surface = surface.convert_format(PixelFormatEnum::RGBA32)?;
+        let (width, height) = surface.size();
+        let pitch = surface.pitch() as usize;
+        let row_length = width as usize * 4;
+
+        let pixels = surface.with_lock(|data| {
+            data.chunks(pitch)
+                .take(height as usize)
+                .flat_map(|row| &row[..row_length])
+                .copied()
+                .collect::<Vec<u8>>()
+        });
+        ImageData::from_rgba(width, height, pixels)
+    }
+
+    /// The image's size in pixels
+    pub fn size(&self) -> Point {
+        Point {
+            x: self.width as f32,
+            y: self.height as f32,
+        }
+    }
+
+    /// Identifies the pixel buffer, which is shared between all clones of the image
+    ///
+    /// The address is only unique while the buffer is alive, so holders of keys must keep the buffer too.
+    fn key(&self) -> usize {
+        self.pixels.as_ptr() as usize
+    }
+}
+
+/// Caches the textures created from images for a window's canvas
+///
+/// Textures of images which weren't drawn during a frame are destroyed by `collect`.
+pub struct ImageCache {
+    texture_creator: TextureCreator<WindowContext>,
+    // Each texture keeps its image's pixels, so their address can't be reused by another image while it's cached
+    textures: HashMap<usize, (Rc<[u8]>, Texture)>,
+    used: HashSet<usize>,
+}
+
+impl ImageCache {
+    pub fn new(texture_creator: TextureCreator<WindowContext>) -> ImageCache {
+        ImageCache {
+            texture_creator,
+            textures: HashMap::new(),
+            used: HashSet::new(),
+        }
+    }
+
+    /// Gets the texture for an image, creating it if it isn't cached yet
+    pub fn get(&mut self, image: &ImageData) -> Result<&Texture, String> {
+        let key = image.key();
+        self.used.insert(key);
+
+        if !self.textures.contains_key(&key) {
+            let mut texture = self
+                .texture_creator
+                .create_texture_static(PixelFormatEnum::RGBA32, image.width, image.height)
+                .map_err(|e| e.to_string())?;
+            texture
+                .update(None, &image.pixels, image.width as usize * 4)
+                .map_err(|e| e.to_string())?;
+            texture.set_blend_mode(BlendMode::Blend);
+            self.textures.insert(key, (image.pixels.clone(), texture));
+        }
+        Ok(&self.textures[&key].1)
+    }
+
+    /// Destroys the textures of all images which weren't drawn since the last collection
+    pub fn collect(&mut self) {
+        let used = std::mem::take(&mut self.used);
+        let unused: Vec<usize> = self
+            .textures
+            .keys()
+            .filter(|x| !used.contains(x))
+            .copied()
+            .collect();
+        for key in unused {
+            if let Some((_, texture)) = self.textures.remove(&key) {
+                // SAFETY: the texture was created by this cache's texture creator, whose renderer is still alive
+                unsafe { texture.destroy() };
+            }
+        }
+    }
+}
+
+impl Drop for ImageCache {
+    fn drop(&mut self) {
+        for (_, (_, texture)) in self.textures.drain() {
+            // SAFETY: the cache is dropped before the canvas which owns the renderer
+            unsafe { texture.destroy() };
+        }
+    }
+}
+
+/// Computes the size an image with the specified stretch occupies inside the available size
+pub fn stretched_size(image_size: Point, available: Point, stretch: Stretch) -> Point {
+    if image_size.x <= 0.0 || image_size.y <= 0.0 {
+        return Point::default();
+    }
+
+    let scale_x = available.x / image_size.x;
+    let scale_y = available.y / image_size.y;
+    match stretch {
+        Stretch::None => image_size,
+        Stretch::Fill => available,
+        Stretch::Uniform => {
+            let scale = scale_x.min(scale_y);
+            Point {
+                x: image_size.x * scale,
+                y: image_size.y * scale,
+            }
+        }
+        Stretch::UniformToFill => {
+            let scale = scale_x.max(scale_y);
+            Point {
+                x: image_size.x * scale,
+                y: image_size.y * scale,
+            }
+        }
+    }
+}
+
+/// Computes the size of the missing axis when only one axis of a uniformly stretched image is constrained
+///
+/// # Arguments
+///
+/// * `image_size`: The image's size in pixels
+/// * `width`: The constrained width, if any
+/// * `height`: The constrained height, if any
+///
+/// returns: Point The image's size with its aspect ratio applied
+pub(crate) fn constrained_size(
+    image_size: Point,
+    width: Option<f32>,
+    height: Option<f32>,
+) -> Point {
+    if image_size.x <= 0.0 || image_size.y <= 0.0 {
+        return Point::default();
+    }
+    match (width, height) {
+        (Some(w), None) => Point {
+            x: w,
+            y: w * image_size.y / image_size.x,
+        },
+        (None, Some(h)) => Point {
+            x: h * image_size.x / image_size.y,
+            y: h,
+        },
+        (Some(w), Some(h)) => Point { x: w, y: h },
+        (None, None) => image_size,
+    }
+}
+
+pub(crate) fn render(
+    bounds: Rect,
+    image: &ImageData,
+    stretch: Stretch,
+    renderer: &mut dyn Renderer,
+) {
+    let size = stretched_size(
+        image.size(),
+        Point {
+            x: bounds.w,
+            y: bounds.h,
+        },
+        stretch,
+    );
+    if size.x < 1.0 || size.y < 1.0 {
+        return;
+    }
+
+    // The image is centered, and only the part overlapping the bounds is drawn
+    let destination = Rect::new(
+        bounds.x + (bounds.w - size.x) / 2.0,
+        bounds.y + (bounds.h - size.y) / 2.0,
+        size.x,
+        size.y,
+    );
+    let left = destination.x.max(bounds.x);
+    let top = destination.y.max(bounds.y);
+    let right = destination.right().min(bounds.right());
+    let bottom = destination.bottom().min(bounds.bottom());
+    if right <= left || bottom <= top {
+        return;
+    }
+    let scale_x = image.width as f32 / size.x;
+    let scale_y = image.height as f32 / size.y;
+    let source = Rect::new(
+        (left - destination.x) * scale_x,
+        (top - destination.y) * scale_y,
+        (right - left) * scale_x,
+        (bottom - top) * scale_y,
+    );
+
+    renderer.draw_image(
+        image,
+        source,
+        Rect::new(left, top, right - left, bottom - top),
+    );
+}
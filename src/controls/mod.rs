@@ -1 +1,7 @@
+pub mod border;
 pub mod control;
+pub mod data_grid;
+pub mod image;
+pub mod progress_bar;
+pub mod rich_text;
+pub mod tree_view;
@@ -0,0 +1,103 @@
+use crate::core::geo::{remap, FlowDirection, Point, Rect};
+use crate::render::Renderer;
+use crate::skin;
+use crate::theme::{Theme, VisualState};
+use std::time::Duration;
+
+/// The size a progress bar asks for when its text doesn't need more room
+const MIN_SIZE: Point = Point { x: 100.0, y: 16.0 };
+
+/// The fraction of the track covered by the indeterminate indicator
+const INDICATOR_FRACTION: f32 = 0.25;
+
+/// The time, in seconds, the indeterminate indicator takes to sweep across the track once
+const SWEEP_DURATION: f32 = 1.5;
+
+/// The animation state of a `Control::ProgressBar`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ProgressBarState {
+    /// The indeterminate animation's progress, in the range 0-1
+    pub phase: f32,
+}
+
+/// Gets the fraction of the range covered by the value, in the range 0-1
+pub fn progress(minimum: f32, maximum: f32, value: f32) -> f32 {
+    if maximum <= minimum {
+        return 0.0;
+    }
+    remap(value.clamp(minimum, maximum), minimum, maximum, 0.0, 1.0)
+}
+
+/// Gets the text shown inside a determinate progress bar
+pub fn percentage_text(minimum: f32, maximum: f32, value: f32) -> String {
+    format!("{}%", (progress(minimum, maximum, value) * 100.0).round())
+}
+
+/// Computes the desired size of a progress bar, given the measured size of its widest possible text
+pub(crate) fn desired_size(text_size: Option<Point>) -> Point {
+    match text_size {
+        Some(size) => Point {
+            x: MIN_SIZE.x.max(size.x + 8.0),
+            y: MIN_SIZE.y.max(size.y),
+        },
+        None => MIN_SIZE,
+    }
+}
+
+/// Advances the indeterminate animation by the elapsed frame time
+pub(crate) fn update(state: &mut ProgressBarState, delta: Duration) {
+    state.phase = (state.phase + delta.as_secs_f32() / SWEEP_DURATION).fract();
+}
+
+/// Draws the indicator inside the track, which is the area inside the control's outline
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render(
+    track: Rect,
+    minimum: f32,
+    maximum: f32,
+    value: f32,
+    indeterminate: bool,
+    state: &ProgressBarState,
+    flow_direction: FlowDirection,
+    theme: &Theme,
+    renderer: &mut dyn Renderer,
+) {
+    let indicator = if indeterminate {
+        // The indicator enters from the left and leaves on the right
+        let width = track.w * INDICATOR_FRACTION;
+        let x = track.x - width + (track.w + width) * state.phase;
+        let left = x.max(track.x);
+        let right = (x + width).min(track.right());
+        Rect {
+            x: left,
+            w: (right - left).max(0.0),
+            ..track
+        }
+    } else {
+        Rect {
+            w: track.w * progress(minimum, maximum, value),
+            ..track
+        }
+    };
+
+    if indicator.w <= 0.0 {
+        return;
+    }
+    // Right-to-left bars fill from the right edge
+    let indicator = match flow_direction {
+        FlowDirection::LeftToRight => indicator,
+        FlowDirection::RightToLeft => indicator.mirror(track),
+    };
+    let skin_part = theme.skin.as_deref().and_then(|skin| {
+        skin.part("progress_bar_indicator", VisualState::Normal)
+            .map(|part| (skin, part))
+    });
+    match skin_part {
+        Some((skin, part)) => {
+            skin::draw_part(skin, part, indicator, renderer);
+        }
+        None => {
+            renderer.fill_rect(indicator, theme.palette.accent);
+        }
+    }
+}
@@ -0,0 +1,512 @@
+use crate::core::geo::{FlowDirection, Point, Rect};
+use crate::core::messages::Message;
+use crate::fonts::{FontKey, TextStyle};
+use crate::render::{Renderer, TextMeasurer};
+use crate::text::{self, TextAlignment, TextWrapping};
+use crate::theme::Theme;
+use sdl2::pixels::Color;
+
+/// A run of text inside a `Control::RichText` which shares one style
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TextSpan {
+    pub text: String,
+
+    /// How the span's text looks. Unspecified properties are taken from the control's text style
+    pub style: TextStyle,
+
+    /// The color painted behind the span's text
+    pub background: Option<Color>,
+
+    /// The target of the span when it's a hyperlink. Links without a color are shown in the theme's accent color and underlined
+    pub link: Option<String>,
+}
+
+impl TextSpan {
+    /// Creates a new span with the specified text and no styling
+    pub fn new(text: &str) -> TextSpan {
+        TextSpan {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// A function which is called with a link's target when the link is clicked
+///
+/// Handlers always compare equal, since function addresses aren't meaningful to compare.
+#[derive(Clone, Copy, Debug)]
+pub struct LinkHandler(pub fn(&str));
+
+impl PartialEq for LinkHandler {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+/// A piece of a span placed on a single line
+#[derive(Clone, PartialEq, Debug)]
+pub struct RichTextFragment {
+    /// The index of the span the fragment belongs to
+    pub span: usize,
+    pub text: String,
+
+    /// The fragment's bounds, relative to the control's top-left corner
+    pub bounds: Rect,
+}
+
+/// Spans broken into fragments which fit inside a region
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RichTextLayout {
+    pub fragments: Vec<RichTextFragment>,
+    pub size: Point,
+}
+
+impl RichTextLayout {
+    /// Gets the index of the span at a position relative to the control's top-left corner
+    pub fn span_at(&self, position: Point) -> Option<usize> {
+        self.fragments
+            .iter()
+            .find(|x| position.inside(x.bounds))
+            .map(|x| x.span)
+    }
+}
+
+/// The interaction state of a `Control::RichText`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct RichTextState {
+    /// The layout computed for the control's current bounds
+    pub layout: RichTextLayout,
+
+    /// The index of the span under the mouse
+    pub hovered_span: Option<usize>,
+
+    /// The index of the link span the primary mouse button was pressed over, until it's released
+    pub pressed_span: Option<usize>,
+}
+
+/// Combines a span's style with the control's, with the span's properties taking precedence
+fn resolve_style(base: &TextStyle, span: &TextSpan, theme: &Theme) -> TextStyle {
+    let link_color = span.link.as_ref().map(|_| theme.palette.accent);
+    TextStyle {
+        family: span.style.family.clone().or_else(|| base.family.clone()),
+        size: span.style.size.or(base.size),
+        bold: base.bold || span.style.bold,
+        italic: base.italic || span.style.italic,
+        underline: base.underline || span.style.underline || span.link.is_some(),
+        strikethrough: base.strikethrough || span.style.strikethrough,
+        color: span.style.color.or(link_color).or(base.color),
+        ..base.clone()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum TokenKind {
+    Word,
+    Space,
+    Newline,
+}
+
+/// Splits a string into words, runs of spaces and newlines
+fn tokenize(text: &str) -> Vec<(TokenKind, &str)> {
+    let mut tokens = vec![];
+    let mut start = 0;
+    let mut kind = None;
+    for (i, c) in text.char_indices() {
+        let current = match c {
+            '\n' => TokenKind::Newline,
+            ' ' => TokenKind::Space,
+            _ => TokenKind::Word,
+        };
+        if kind != Some(current) || current == TokenKind::Newline {
+            if let Some(kind) = kind {
+                tokens.push((kind, &text[start..i]));
+            }
+            start = i;
+            kind = Some(current);
+        }
+    }
+    if let Some(kind) = kind {
+        tokens.push((kind, &text[start..]));
+    }
+    tokens
+}
+
+/// A fragment whose line hasn't been positioned yet
+struct Piece {
+    span: usize,
+    text: String,
+    width: f32,
+}
+
+/// Appends text to a line, merging it into the last piece when both belong to the same span
+fn push_piece(
+    line: &mut Vec<Piece>,
+    span: usize,
+    text: &str,
+    font: &FontKey,
+    measurer: &mut dyn TextMeasurer,
+) {
+    match line.last_mut() {
+        Some(last) if last.span == span => {
+            last.text.push_str(text);
+            last.width = text::measure(&last.text, font, measurer).x;
+        }
+        _ => line.push(Piece {
+            span,
+            text: text.to_string(),
+            width: text::measure(text, font, measurer).x,
+        }),
+    }
+}
+
+/// Removes trailing spaces from a finished line, so they don't affect its width
+fn trim_line(line: &mut Vec<Piece>, fonts_of_spans: &[FontKey], measurer: &mut dyn TextMeasurer) {
+    while let Some(last) = line.last_mut() {
+        let trimmed = last.text.trim_end_matches(' ');
+        if trimmed.len() == last.text.len() {
+            break;
+        }
+        if trimmed.is_empty() {
+            line.pop();
+            continue;
+        }
+        last.text = trimmed.to_string();
+        last.width = text::measure(&last.text, &fonts_of_spans[last.span], measurer).x;
+        break;
+    }
+}
+
+/// Breaks spans into fragments, wrapping across span boundaries
+///
+/// # Arguments
+///
+/// * `spans`: The spans to lay out
+/// * `style`: The control's text style, which provides the wrapping, alignment and line height
+/// * `max_width`: The width of the region. Lines are only aligned when it's finite
+/// * `flow_direction`: The direction the control flows in, which decides where lines start
+/// * `measurer`: Measures the spans' text
+/// * `theme`: The theme providing unspecified font properties
+///
+/// returns: RichTextLayout
+pub(crate) fn layout(
+    spans: &[TextSpan],
+    style: &TextStyle,
+    max_width: f32,
+    flow_direction: FlowDirection,
+    measurer: &mut dyn TextMeasurer,
+    theme: &Theme,
+) -> RichTextLayout {
+    let span_fonts: Vec<FontKey> = spans
+        .iter()
+        .map(|x| resolve_style(style, x, theme).font_key(theme))
+        .collect();
+    let wraps = style.wrapping != TextWrapping::None;
+
+    let mut lines: Vec<Vec<Piece>> = vec![vec![]];
+    let mut line_width = 0.0;
+    for (i, span) in spans.iter().enumerate() {
+        let font = &span_fonts[i];
+        for (kind, token) in tokenize(&span.text) {
+            let line = lines.last_mut().unwrap();
+            match kind {
+                TokenKind::Newline => {
+                    trim_line(line, &span_fonts, measurer);
+                    lines.push(vec![]);
+                    line_width = 0.0;
+                }
+                // Spaces at the start of wrapped lines are dropped
+                TokenKind::Space if wraps && line.is_empty() => {}
+                TokenKind::Space => {
+                    line_width += text::measure(token, font, measurer).x;
+                    push_piece(line, i, token, font, measurer);
+                }
+                TokenKind::Word => {
+                    let width = text::measure(token, font, measurer).x;
+                    let breaks_characters =
+                        style.wrapping == TextWrapping::Character || (wraps && width > max_width);
+                    let pieces: Vec<String> = if breaks_characters {
+                        token.chars().map(String::from).collect()
+                    } else {
+                        vec![token.to_string()]
+                    };
+
+                    for piece in pieces {
+                        let width = text::measure(&piece, font, measurer).x;
+                        let line = lines.last_mut().unwrap();
+                        if wraps && !line.is_empty() && line_width + width > max_width {
+                            trim_line(line, &span_fonts, measurer);
+                            lines.push(vec![]);
+                            line_width = 0.0;
+                        }
+                        line_width += width;
+                        push_piece(lines.last_mut().unwrap(), i, &piece, font, measurer);
+                    }
+                }
+            }
+        }
+    }
+    if let Some(line) = lines.last_mut() {
+        trim_line(line, &span_fonts, measurer);
+    }
+
+    // Fragments on one line share a baseline, set by the line's tallest font
+    let default_font = style.font_key(theme);
+    let mut layout = RichTextLayout::default();
+    let mut y = 0.0;
+    for line in lines {
+        let mut ascent = measurer.ascent(&default_font);
+        let mut line_height = measurer.line_spacing(&default_font);
+        if !line.is_empty() {
+            ascent = line
+                .iter()
+                .map(|x| measurer.ascent(&span_fonts[x.span]))
+                .fold(0.0, f32::max);
+            line_height = line
+                .iter()
+                .map(|x| measurer.line_spacing(&span_fonts[x.span]))
+                .fold(0.0, f32::max);
+        }
+        let line_height = style.line_height.unwrap_or(line_height);
+
+        let width: f32 = line.iter().map(|x| x.width).sum();
+        let line_x = match style.alignment.resolve(flow_direction) {
+            _ if !max_width.is_finite() => 0.0,
+            TextAlignment::Start => 0.0,
+            TextAlignment::Center => (max_width - width) / 2.0,
+            TextAlignment::End => max_width - width,
+        };
+        // Right-to-left lines place their first span at the right edge
+        let mut offset = 0.0;
+        for piece in line {
+            let x = match flow_direction {
+                FlowDirection::LeftToRight => line_x + offset,
+                FlowDirection::RightToLeft => line_x + width - offset - piece.width,
+            };
+            let font = &span_fonts[piece.span];
+            let height = measurer.height(font);
+            layout.fragments.push(RichTextFragment {
+                span: piece.span,
+                bounds: Rect::new(x, y + ascent - measurer.ascent(font), piece.width, height),
+                text: piece.text,
+            });
+            offset += piece.width;
+        }
+
+        layout.size.x = layout.size.x.max(width);
+        y += line_height;
+    }
+    layout.size.y = y;
+    layout
+}
+
+pub(crate) fn process_message(
+    bounds: Rect,
+    spans: &[TextSpan],
+    on_link: LinkHandler,
+    state: &mut RichTextState,
+    message: Message,
+) {
+    let span_at = |position: Point| {
+        if !position.inside(bounds) {
+            return None;
+        }
+        state.layout.span_at(Point {
+            x: position.x - bounds.x,
+            y: position.y - bounds.y,
+        })
+    };
+    match message {
+        Message::MouseMove(position) => {
+            state.hovered_span = span_at(position);
+        }
+        Message::MouseDown(position, _) => {
+            state.pressed_span =
+                span_at(position).filter(|x| spans.get(*x).is_some_and(|x| x.link.is_some()));
+        }
+        // Links are followed when the button is released over the link it was pressed over
+        Message::MouseUp(position) => {
+            let pressed = state.pressed_span.take();
+            if pressed.is_some() && span_at(position) == pressed {
+                // The spans may have been replaced since the layout the span was found in
+                if let Some(link) = pressed.and_then(|x| spans.get(x)?.link.as_ref()) {
+                    (on_link.0)(link);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render(
+    bounds: Rect,
+    spans: &[TextSpan],
+    style: &TextStyle,
+    text_color: Color,
+    flow_direction: FlowDirection,
+    state: &RichTextState,
+    theme: &Theme,
+    renderer: &mut dyn Renderer,
+) {
+    for fragment in &state.layout.fragments {
+        let Some(span) = spans.get(fragment.span) else {
+            continue;
+        };
+        let span_style = resolve_style(style, span, theme);
+        let fragment_bounds = Rect {
+            x: bounds.x + fragment.bounds.x,
+            y: bounds.y + fragment.bounds.y,
+            ..fragment.bounds
+        };
+        // Links under the mouse are highlighted, more strongly while pressed
+        let highlight = if span.link.is_none() {
+            None
+        } else if state.pressed_span == Some(fragment.span) {
+            Some(theme.palette.control_pressed)
+        } else if state.hovered_span == Some(fragment.span) {
+            Some(theme.palette.control_hover)
+        } else {
+            None
+        };
+        if let Some(background) = highlight.or(span.background) {
+            renderer.fill_rect(fragment_bounds, background);
+        }
+        text::draw_text(
+            &fragment.text,
+            fragment_bounds.top_left(),
+            &span_style.font_key(theme),
+            span_style.color.unwrap_or(text_color),
+            flow_direction.into(),
+            renderer,
+        );
+    }
+}
+
+/// Parses a color in the `#rrggbb` or `#rrggbbaa` format
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if !hex.is_ascii() || (hex.len() != 6 && hex.len() != 8) {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+    Some(Color::RGBA(channel(0)?, channel(2)?, channel(4)?, alpha))
+}
+
+/// Applies an opening tag to a span template
+fn apply_tag(span: &mut TextSpan, name: &str, value: Option<&str>) -> Result<(), String> {
+    let value_of = |name: &str| value.ok_or_else(|| format!("`{}` requires a value", name));
+    let color_of = |name: &str| {
+        value_of(name).and_then(|x| parse_color(x).ok_or_else(|| format!("invalid color `{}`", x)))
+    };
+    match name {
+        "b" => span.style.bold = true,
+        "i" => span.style.italic = true,
+        "u" => span.style.underline = true,
+        "s" => span.style.strikethrough = true,
+        "color" => span.style.color = Some(color_of(name)?),
+        "bg" => span.background = Some(color_of(name)?),
+        "font" => span.style.family = Some(value_of(name)?.to_string()),
+        "size" => {
+            let size = value_of(name)?;
+            span.style.size = Some(
+                size.parse()
+                    .map_err(|_| format!("invalid size `{}`", size))?,
+            );
+        }
+        "link" => span.link = Some(value_of(name)?.to_string()),
+        _ => return Err(format!("unknown tag `{}`", name)),
+    }
+    Ok(())
+}
+
+/// Parses spans from a small markup syntax
+///
+/// Tags enclose the text they apply to, and can be nested:
+///
+/// ```text
+/// [b]Bold[/b], [i]italic[/i], [u]underlined[/u] and [s]struck[/s] text
+/// [color=#ff0000]Red[/color] text on a [bg=#ffff00]highlighted[/bg] background
+/// [font=Consolas]Monospace[/font] text at [size=20]another size[/size]
+/// A [link=https://example.com]hyperlink[/link]
+/// ```
+///
+/// `[[` stands for a literal `[`.
+///
+/// # Arguments
+///
+/// * `markup`: The markup to parse
+///
+/// returns: Result<Vec<TextSpan>, String> An error describing the first malformed or mismatched tag
+pub fn parse_markup(markup: &str) -> Result<Vec<TextSpan>, String> {
+    let mut spans: Vec<TextSpan> = vec![];
+    let mut stack: Vec<(String, TextSpan)> = vec![];
+    let mut text = String::new();
+
+    let flush = |text: &mut String, spans: &mut Vec<TextSpan>, template: &TextSpan| {
+        if text.is_empty() {
+            return;
+        }
+        match spans.last_mut() {
+            Some(last)
+                if last.style == template.style
+                    && last.background == template.background
+                    && last.link == template.link =>
+            {
+                last.text.push_str(text);
+            }
+            _ => spans.push(TextSpan {
+                text: text.clone(),
+                ..template.clone()
+            }),
+        }
+        text.clear();
+    };
+
+    let mut rest = markup;
+    while let Some(c) = rest.chars().next() {
+        if c != '[' {
+            text.push(c);
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("[[") {
+            text.push('[');
+            rest = after;
+            continue;
+        }
+
+        let end = rest
+            .find(']')
+            .ok_or_else(|| format!("unterminated tag at `{}`", rest))?;
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+
+        let template = stack.last().map(|x| x.1.clone()).unwrap_or_default();
+        flush(&mut text, &mut spans, &template);
+
+        if let Some(name) = tag.strip_prefix('/') {
+            match stack.pop() {
+                Some((open, _)) if open == name => {}
+                Some((open, _)) => {
+                    return Err(format!("`[/{}]` closes `[{}]`", name, open));
+                }
+                None => return Err(format!("`[/{}]` closes nothing", name)),
+            }
+        } else {
+            let (name, value) = match tag.split_once('=') {
+                Some((name, value)) => (name, Some(value)),
+                None => (tag, None),
+            };
+            let mut span = template;
+            apply_tag(&mut span, name, value)?;
+            stack.push((name.to_string(), span));
+        }
+    }
+
+    if let Some((open, _)) = stack.last() {
+        return Err(format!("`[{}]` is never closed", open));
+    }
+    flush(&mut text, &mut spans, &TextSpan::default());
+    Ok(spans)
+}
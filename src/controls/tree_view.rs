@@ -0,0 +1,435 @@
+use crate::controls::control::{Change, SelectionMode};
+use crate::core::geo::{FlowDirection, Point, Rect};
+use crate::core::messages::Message;
+use crate::fonts::FontKey;
+use crate::render::Renderer;
+use crate::text;
+use crate::theme::{Style, Theme};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::pixels::Color;
+
+/// The horizontal offset applied to each nesting level
+pub const TREE_VIEW_INDENT: f32 = 16.0;
+
+/// The size of an item's expand/collapse box
+const EXPANDER_SIZE: f32 = 9.0;
+
+/// Identifies an item by the indices leading to it from the root items
+pub type TreePath = Vec<usize>;
+
+/// An item inside a `Control::TreeView`
+#[derive(Clone, Debug, Default)]
+pub struct TreeViewItem {
+    /// The item's text
+    pub text: String,
+
+    /// Whether the item's children are shown
+    pub expanded: bool,
+
+    /// The item's children
+    pub children: Vec<TreeViewItem>,
+
+    /// A function which produces the item's children the first time it's expanded
+    ///
+    /// Once invoked, the result replaces `children` and the loader is cleared.
+    pub load_children: Option<fn(&TreeViewItem) -> Vec<TreeViewItem>>,
+}
+
+impl TreeViewItem {
+    /// Creates a new item with the specified text and no children
+    pub fn new(text: &str) -> TreeViewItem {
+        TreeViewItem {
+            text: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Whether the item has, or can lazily produce, children
+    pub fn is_expandable(&self) -> bool {
+        !self.children.is_empty() || self.load_children.is_some()
+    }
+
+    /// Expands the item, loading its children first if necessary
+    pub fn expand(&mut self) {
+        if let Some(load_children) = self.load_children.take() {
+            self.children = load_children(self);
+        }
+        self.expanded = true;
+    }
+
+    /// Collapses the item
+    pub fn collapse(&mut self) {
+        self.expanded = false;
+    }
+}
+
+// Loaders are compared by presence only, since function addresses aren't meaningful to compare
+impl PartialEq for TreeViewItem {
+    fn eq(&self, other: &Self) -> bool {
+        self.text == other.text
+            && self.expanded == other.expanded
+            && self.children == other.children
+            && self.load_children.is_some() == other.load_children.is_some()
+    }
+}
+
+/// The interaction state of a `Control::TreeView`
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TreeViewState {
+    /// The paths of all selected items
+    ///
+    /// Collapsing an item through the tree view deselects its descendants, so no hidden item stays selected.
+    pub selected: Vec<TreePath>,
+
+    /// The item which shift-selected ranges extend from
+    pub anchor: Option<TreePath>,
+
+    /// The item which keyboard navigation operates on
+    pub cursor: Option<TreePath>,
+
+    /// Whether the tree view receives keyboard input
+    pub focused: bool,
+}
+
+/// A row produced by flattening the expanded parts of a tree
+pub(crate) struct VisibleRow<'a> {
+    pub path: TreePath,
+    pub item: &'a TreeViewItem,
+}
+
+impl VisibleRow<'_> {
+    pub fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+}
+
+/// Flattens all items which aren't hidden inside a collapsed parent, in display order
+pub(crate) fn visible_rows(items: &[TreeViewItem]) -> Vec<VisibleRow<'_>> {
+    fn visit<'a>(items: &'a [TreeViewItem], prefix: &TreePath, rows: &mut Vec<VisibleRow<'a>>) {
+        for (i, item) in items.iter().enumerate() {
+            let mut path = prefix.clone();
+            path.push(i);
+            rows.push(VisibleRow {
+                path: path.clone(),
+                item,
+            });
+            if item.expanded {
+                visit(&item.children, &path, rows);
+            }
+        }
+    }
+
+    let mut rows = vec![];
+    visit(items, &vec![], &mut rows);
+    rows
+}
+
+/// Gets the item at the specified path
+pub fn item_at_mut<'a>(
+    items: &'a mut [TreeViewItem],
+    path: &[usize],
+) -> Option<&'a mut TreeViewItem> {
+    let (first, rest) = path.split_first()?;
+    let item = items.get_mut(*first)?;
+    if rest.is_empty() {
+        Some(item)
+    } else {
+        item_at_mut(&mut item.children, rest)
+    }
+}
+
+/// Expands or collapses the item at `path`, returning `Layout` if it did either
+fn toggle_expansion(
+    items: &mut [TreeViewItem],
+    state: &mut TreeViewState,
+    path: &[usize],
+) -> Change {
+    let Some(item) = item_at_mut(items, path) else {
+        return Change::None;
+    };
+    if item.expanded {
+        item.collapse();
+
+        // Descendants become hidden, so they're deselected, and the cursor and anchor move up to the collapsed item
+        let hidden = |x: &TreePath| x.len() > path.len() && x.starts_with(path);
+        state.selected.retain(|x| !hidden(x));
+        if state.cursor.as_ref().is_some_and(hidden) {
+            state.cursor = Some(path.to_vec());
+        }
+        if state.anchor.as_ref().is_some_and(hidden) {
+            state.anchor = Some(path.to_vec());
+        }
+        Change::Layout
+    } else if item.is_expandable() {
+        item.expand();
+        Change::Layout
+    } else {
+        Change::None
+    }
+}
+
+/// Selects the item at `path` according to the selection mode and the held modifiers
+///
+/// Shift extends the selection over the visible rows between the anchor and `path`. The selection is drawn, so this
+/// always returns `Appearance`.
+fn select(
+    state: &mut TreeViewState,
+    selection_mode: &SelectionMode,
+    rows: &[VisibleRow],
+    path: TreePath,
+    keymod: Mod,
+) -> Change {
+    let ctrl = keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD);
+    let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+    let row_of = |path: &TreePath| rows.iter().position(|row| row.path == *path);
+    let range = match (&state.anchor, shift) {
+        (Some(anchor), true) => row_of(anchor).zip(row_of(&path)),
+        _ => None,
+    };
+
+    if *selection_mode == SelectionMode::Multiple && ctrl {
+        if let Some(index) = state.selected.iter().position(|x| *x == path) {
+            state.selected.remove(index);
+        } else {
+            state.selected.push(path.clone());
+        }
+        state.anchor = Some(path.clone());
+    } else if let (SelectionMode::Multiple, Some((anchor, target))) = (selection_mode, range) {
+        state.selected = rows[anchor.min(target)..=anchor.max(target)]
+            .iter()
+            .map(|row| row.path.clone())
+            .collect();
+    } else {
+        state.selected = vec![path.clone()];
+        state.anchor = Some(path.clone());
+    }
+    state.cursor = Some(path);
+    Change::Appearance
+}
+
+/// Computes the desired size of a tree view, given a function which measures an item's text
+pub(crate) fn desired_size(
+    items: &[TreeViewItem],
+    row_height: f32,
+    mut measure: impl FnMut(&str) -> Point,
+) -> Point {
+    let rows = visible_rows(items);
+    let width = rows
+        .iter()
+        .map(|row| (row.depth() + 1) as f32 * TREE_VIEW_INDENT + measure(&row.item.text).x)
+        .fold(0.0, f32::max);
+
+    Point {
+        x: width,
+        y: rows.len() as f32 * row_height,
+    }
+}
+
+/// Handles a message sent to a tree view
+///
+/// returns: Change `Layout` if items were expanded or collapsed, and `Appearance` if only the selection or focus changed
+pub(crate) fn process_message(
+    bounds: Rect,
+    items: &mut [TreeViewItem],
+    selection_mode: &SelectionMode,
+    row_height: f32,
+    state: &mut TreeViewState,
+    message: Message,
+) -> Change {
+    match message {
+        Message::MouseDown(position, keymod) => {
+            let focused = position.inside(bounds);
+            let focus_change = Change::appearance_if(state.focused != focused);
+            state.focused = focused;
+            if !state.focused || row_height <= 0.0 {
+                return focus_change;
+            }
+
+            let index = ((position.y - bounds.y) / row_height) as usize;
+            let rows = visible_rows(items);
+            let Some((path, depth)) = rows.get(index).map(|row| (row.path.clone(), row.depth()))
+            else {
+                return focus_change;
+            };
+
+            // Clicks on the indentation area of an item toggle its expansion instead of selecting it
+            if position.x < bounds.x + (depth + 1) as f32 * TREE_VIEW_INDENT {
+                let cursor_change = Change::appearance_if(state.cursor.as_ref() != Some(&path));
+                state.cursor = Some(path.clone());
+                toggle_expansion(items, state, &path)
+                    .max(cursor_change)
+                    .max(focus_change)
+            } else {
+                select(state, selection_mode, &rows, path, keymod)
+            }
+        }
+        Message::KeyDown(keycode, keymod) => {
+            if !state.focused {
+                return Change::None;
+            }
+
+            let rows = visible_rows(items);
+            if rows.is_empty() {
+                return Change::None;
+            }
+            let cursor_index = state
+                .cursor
+                .as_ref()
+                .and_then(|cursor| rows.iter().position(|row| row.path == *cursor));
+            let Some(cursor_index) = cursor_index else {
+                let path = rows[0].path.clone();
+                return select(state, selection_mode, &rows, path, keymod);
+            };
+            let cursor = rows[cursor_index].path.clone();
+            let expandable = rows[cursor_index].item.is_expandable();
+            let expanded = rows[cursor_index].item.expanded;
+            let has_children = !rows[cursor_index].item.children.is_empty();
+            let previous = cursor_index.checked_sub(1).map(|i| rows[i].path.clone());
+            let next = rows.get(cursor_index + 1).map(|row| row.path.clone());
+
+            match keycode {
+                Keycode::Up => match previous {
+                    Some(path) => select(state, selection_mode, &rows, path, keymod),
+                    None => Change::None,
+                },
+                Keycode::Down => match next {
+                    Some(path) => select(state, selection_mode, &rows, path, keymod),
+                    None => Change::None,
+                },
+                Keycode::Left => {
+                    if expanded {
+                        toggle_expansion(items, state, &cursor)
+                    } else if cursor.len() > 1 {
+                        let parent = cursor[..cursor.len() - 1].to_vec();
+                        select(state, selection_mode, &rows, parent, keymod)
+                    } else {
+                        Change::None
+                    }
+                }
+                Keycode::Right => {
+                    if !expanded && expandable {
+                        toggle_expansion(items, state, &cursor)
+                    } else if expanded && has_children {
+                        let mut child = cursor.clone();
+                        child.push(0);
+                        select(state, selection_mode, &rows, child, keymod)
+                    } else {
+                        Change::None
+                    }
+                }
+                Keycode::Space => select(state, selection_mode, &rows, cursor, keymod),
+                _ => Change::None,
+            }
+        }
+        _ => Change::None,
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render(
+    bounds: Rect,
+    items: &[TreeViewItem],
+    row_height: f32,
+    state: &TreeViewState,
+    theme: &Theme,
+    style: &Style,
+    font: &FontKey,
+    text_color: Color,
+    flow_direction: FlowDirection,
+    renderer: &mut dyn Renderer,
+) {
+    // Right-to-left trees indent from the right edge
+    let flow_x = |x: f32| match flow_direction {
+        FlowDirection::LeftToRight => x,
+        FlowDirection::RightToLeft => bounds.x + bounds.right() - x,
+    };
+    for (i, row) in visible_rows(items).iter().enumerate() {
+        let row_rect = Rect {
+            x: bounds.x,
+            y: bounds.y + i as f32 * row_height,
+            w: bounds.w,
+            h: row_height,
+        };
+        if row_rect.y >= bounds.bottom() {
+            break;
+        }
+
+        if state.selected.contains(&row.path) {
+            renderer.fill_rect(row_rect, theme.palette.selection);
+        }
+        if state.focused && state.cursor.as_ref() == Some(&row.path) {
+            renderer.stroke_rect(row_rect, theme.palette.accent);
+        }
+
+        // Indentation guides, one per ancestor level
+        for level in 0..row.depth() {
+            let x = flow_x(bounds.x + level as f32 * TREE_VIEW_INDENT + TREE_VIEW_INDENT / 2.0);
+            renderer.draw_line(
+                Point { x, y: row_rect.y },
+                Point {
+                    x,
+                    y: row_rect.bottom(),
+                },
+                theme.palette.border,
+            );
+        }
+
+        if row.item.is_expandable() {
+            let center = Point {
+                x: flow_x(
+                    bounds.x + row.depth() as f32 * TREE_VIEW_INDENT + TREE_VIEW_INDENT / 2.0,
+                ),
+                y: row_rect.y + row_height / 2.0,
+            };
+            let expander = Rect::new(
+                center.x - EXPANDER_SIZE / 2.0,
+                center.y - EXPANDER_SIZE / 2.0,
+                EXPANDER_SIZE,
+                EXPANDER_SIZE,
+            );
+            renderer.stroke_rect(expander, style.foreground);
+            renderer.draw_line(
+                Point {
+                    x: expander.x + 2.0,
+                    y: center.y,
+                },
+                Point {
+                    x: expander.right() - 3.0,
+                    y: center.y,
+                },
+                style.foreground,
+            );
+            if !row.item.expanded {
+                renderer.draw_line(
+                    Point {
+                        x: center.x,
+                        y: expander.y + 2.0,
+                    },
+                    Point {
+                        x: center.x,
+                        y: expander.bottom() - 3.0,
+                    },
+                    style.foreground,
+                );
+            }
+        }
+
+        // The text starts after the expander column, centered vertically inside the row
+        let text_size = text::measure(&row.item.text, font, renderer);
+        let text_start = flow_x(bounds.x + (row.depth() + 1) as f32 * TREE_VIEW_INDENT);
+        text::draw_text(
+            &row.item.text,
+            Point {
+                x: match flow_direction {
+                    FlowDirection::LeftToRight => text_start,
+                    FlowDirection::RightToLeft => text_start - text_size.x,
+                },
+                y: row_rect.y + (row_height - text_size.y) / 2.0,
+            },
+            font,
+            text_color,
+            flow_direction.into(),
+            renderer,
+        );
+    }
+}
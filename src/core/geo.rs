@@ -1,7 +1,7 @@
 use sdl2::rect::Point as SdlPoint;
 use sdl2::rect::Rect as SdlRect;
 
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -29,6 +29,14 @@ impl Point {
     pub fn dist(&self, other: Point) -> f32 {
         ((other.x - self.x).powf(2.0) + (other.y - self.y).powf(2.0)).sqrt()
     }
+
+    /// Reflects the point across the vertical line through the center of a rect
+    pub fn mirror(&self, rect: Rect) -> Point {
+        Point {
+            x: rect.x + rect.right() - self.x,
+            y: self.y,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Default, PartialEq, Debug)]
@@ -115,11 +123,76 @@ impl Rect {
         }
     }
 
+    pub fn deflate(&self, thickness: Thickness) -> Rect {
+        Rect {
+            x: self.x + thickness.left,
+            y: self.y + thickness.top,
+            w: (self.w - thickness.horizontal()).max(0.0),
+            h: (self.h - thickness.vertical()).max(0.0),
+        }
+    }
+
+    /// Gets the area shared by two rects
+    ///
+    /// Rects which don't overlap produce an empty rect.
+    pub fn intersect(&self, other: Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        Rect {
+            x,
+            y,
+            w: (self.right().min(other.right()) - x).max(0.0),
+            h: (self.bottom().min(other.bottom()) - y).max(0.0),
+        }
+    }
+
+    /// Reflects the rect across the vertical line through the center of another rect
+    pub fn mirror(&self, rect: Rect) -> Rect {
+        Rect {
+            x: rect.x + rect.right() - self.right(),
+            ..*self
+        }
+    }
+
     pub fn to_sdl(&self) -> SdlRect {
         SdlRect::new(self.x as i32, self.y as i32, self.w as u32, self.h as u32)
     }
 }
 
+/// The size of each edge of a rectangular frame, such as padding
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Thickness {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Thickness {
+    pub fn new(left: f32, top: f32, right: f32, bottom: f32) -> Thickness {
+        Thickness {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+    pub fn uniform(v: f32) -> Thickness {
+        Thickness::new(v, v, v, v)
+    }
+    pub fn horizontal(&self) -> f32 {
+        self.left + self.right
+    }
+    pub fn vertical(&self) -> f32 {
+        self.top + self.bottom
+    }
+
+    /// Swaps the left and right edges
+    pub fn mirror(&self) -> Thickness {
+        Thickness::new(self.right, self.top, self.left, self.bottom)
+    }
+}
+
 pub fn remap(value: f32, from1: f32, to1: f32, from2: f32, to2: f32) -> f32 {
     (value - from1) / (to1 - from1) * (to2 - from2) + from2
 }
@@ -132,3 +205,24 @@ pub enum Alignment {
     End,
     Fill,
 }
+
+/// Describes the horizontal direction in which content flows
+///
+/// Right-to-left flow swaps `Alignment::Start` and `Alignment::End`, reverses horizontal stacks and mirrors direction-aware controls.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
+pub enum FlowDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+impl FlowDirection {
+    /// Maps an alignment to the one it has in this direction
+    pub fn align(&self, alignment: Alignment) -> Alignment {
+        match (self, alignment) {
+            (FlowDirection::RightToLeft, Alignment::Start) => Alignment::End,
+            (FlowDirection::RightToLeft, Alignment::End) => Alignment::Start,
+            _ => alignment,
+        }
+    }
+}
@@ -1,6 +1,96 @@
 use crate::core::geo::{Point, Rect};
+use sdl2::event::Event;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
 
 #[derive(Clone, Debug)]
 pub enum Message {
     MouseMove(Point),
+
+    /// The primary mouse button was pressed at the specified position
+    MouseDown(Point, Mod),
+
+    /// The primary mouse button was released at the specified position
+    MouseUp(Point),
+
+    /// The mouse wheel was scrolled by the specified amount
+    MouseWheel(Point),
+
+    /// A key was pressed
+    KeyDown(Keycode, Mod),
+
+    /// Text was typed, after the keyboard layout and input method were applied
+    TextInput(String),
+}
+
+impl Message {
+    /// Translates an SDL event into the message controls receive for it
+    ///
+    /// # Arguments
+    ///
+    /// * `event`: The event
+    /// * `modifiers`: The modifier keys currently held, which mouse button events don't carry
+    ///
+    /// returns: Option<Message> `None` for events controls don't handle
+    pub fn from_event(event: &Event, modifiers: Mod) -> Option<Message> {
+        match event {
+            Event::MouseMotion { x, y, .. } => Some(Message::MouseMove(Point {
+                x: *x as f32,
+                y: *y as f32,
+            })),
+            Event::MouseButtonDown {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => Some(Message::MouseDown(
+                Point {
+                    x: *x as f32,
+                    y: *y as f32,
+                },
+                modifiers,
+            )),
+            Event::MouseButtonUp {
+                mouse_btn: MouseButton::Left,
+                x,
+                y,
+                ..
+            } => Some(Message::MouseUp(Point {
+                x: *x as f32,
+                y: *y as f32,
+            })),
+            Event::MouseWheel { x, y, .. } => Some(Message::MouseWheel(Point {
+                x: *x as f32,
+                y: *y as f32,
+            })),
+            Event::KeyDown {
+                keycode: Some(keycode),
+                keymod,
+                ..
+            } => Some(Message::KeyDown(*keycode, *keymod)),
+            Event::TextInput { text, .. } => Some(Message::TextInput(text.clone())),
+            _ => None,
+        }
+    }
+
+    /// Reflects the message's position across the vertical line through the center of a rect
+    ///
+    /// Horizontal wheel movement is reversed, so mirrored controls scroll along with the mouse, and the left and right
+    /// arrow keys are swapped, so they keep pointing the way they do on screen.
+    pub fn mirror(&self, rect: Rect) -> Message {
+        match self {
+            Message::MouseMove(position) => Message::MouseMove(position.mirror(rect)),
+            Message::MouseDown(position, keymod) => {
+                Message::MouseDown(position.mirror(rect), *keymod)
+            }
+            Message::MouseUp(position) => Message::MouseUp(position.mirror(rect)),
+            Message::MouseWheel(delta) => Message::MouseWheel(Point {
+                x: -delta.x,
+                y: delta.y,
+            }),
+            Message::KeyDown(Keycode::Left, keymod) => Message::KeyDown(Keycode::Right, *keymod),
+            Message::KeyDown(Keycode::Right, keymod) => Message::KeyDown(Keycode::Left, *keymod),
+            Message::KeyDown(..) | Message::TextInput(_) => self.clone(),
+        }
+    }
 }
@@ -0,0 +1,371 @@
+use crate::core::geo::Point;
+use crate::render::TextMeasurer;
+use crate::text::{TextAlignment, TextTrimming, TextWrapping};
+use crate::theme::Theme;
+use sdl2::pixels::Color;
+use sdl2::rwops::RWops;
+use sdl2::ttf::{Font, FontStyle, Sdl2TtfContext};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+/// The family of the font embedded in the crate, which is used when a requested family isn't registered
+pub const DEFAULT_FONT_FAMILY: &str = "Segoe UI";
+
+static DEFAULT_FONT: &[u8] = include_bytes!("skin/segoe.ttf");
+
+static TTF_CONTEXT: OnceLock<Result<Sdl2TtfContext, String>> = OnceLock::new();
+
+/// Gets the SDL_ttf context shared by every font in the program, initializing SDL_ttf the first time
+///
+/// The context lives for the rest of the program, so fonts loaded with it can be kept around. It's never dropped, as
+/// dropping any context shuts SDL_ttf down for all of them.
+///
+/// returns: Result<&Sdl2TtfContext, String> An error if SDL_ttf couldn't be initialized
+pub fn ttf_context() -> Result<&'static Sdl2TtfContext, String> {
+    TTF_CONTEXT
+        .get_or_init(|| sdl2::ttf::init().map_err(|e| e.to_string()))
+        .as_ref()
+        .map_err(Clone::clone)
+}
+
+/// The contents of a font file, either embedded in the program or read at runtime
+///
+/// Cloning is cheap, as read contents are shared between clones.
+#[derive(Clone, Debug)]
+enum FontData {
+    Static(&'static [u8]),
+    Shared(Rc<[u8]>),
+}
+
+impl FontData {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            FontData::Static(data) => data,
+            FontData::Shared(data) => data,
+        }
+    }
+}
+
+/// Maps font family names to their font file contents
+#[derive(Clone, Debug)]
+pub struct FontRegistry {
+    families: HashMap<String, FontData>,
+    fallbacks: Vec<String>,
+}
+
+impl FontRegistry {
+    /// Creates a registry containing only the embedded default font
+    pub fn new() -> FontRegistry {
+        let mut families = HashMap::new();
+        families.insert(
+            DEFAULT_FONT_FAMILY.to_string(),
+            FontData::Static(DEFAULT_FONT),
+        );
+        FontRegistry {
+            families,
+            fallbacks: vec![],
+        }
+    }
+
+    /// Registers a font family from the contents of a font file, replacing any previous registration
+    ///
+    /// # Arguments
+    ///
+    /// * `family`: The family name, as referenced by themes
+    /// * `data`: The contents of a TTF or OTF file
+    pub fn register(&mut self, family: &str, data: &'static [u8]) {
+        self.families
+            .insert(family.to_string(), FontData::Static(data));
+    }
+
+    /// Registers a font family from font data produced at runtime, such as a downloaded or decompressed file
+    ///
+    /// The data is kept in memory while the registry, or fonts loaded from it, are alive.
+    ///
+    /// # Arguments
+    ///
+    /// * `family`: The family name, as referenced by themes
+    /// * `data`: The contents of a TTF or OTF file
+    pub fn register_bytes(&mut self, family: &str, data: impl Into<Rc<[u8]>>) {
+        self.families
+            .insert(family.to_string(), FontData::Shared(data.into()));
+    }
+
+    /// Registers a font family from a font file
+    ///
+    /// The file's contents are kept in memory while the registry, or fonts loaded from it, are alive.
+    ///
+    /// # Arguments
+    ///
+    /// * `family`: The family name, as referenced by themes
+    /// * `path`: The path to a TTF or OTF file
+    ///
+    /// returns: Result<(), String> An error if the file can't be read
+    pub fn register_file<P: AsRef<Path>>(&mut self, family: &str, path: P) -> Result<(), String> {
+        let data = fs::read(path.as_ref())
+            .map_err(|e| format!("Failed to read font {}: {}", path.as_ref().display(), e))?;
+        self.register_bytes(family, data);
+        Ok(())
+    }
+
+    /// Appends a family to the chain consulted for characters missing from a font
+    ///
+    /// The chain is tried in order, after the font itself and before the default font.
+    pub fn add_fallback(&mut self, family: &str) {
+        if !self.fallbacks.iter().any(|x| x == family) {
+            self.fallbacks.push(family.to_string());
+        }
+    }
+
+    /// The families consulted for missing characters, in order
+    pub fn fallbacks(&self) -> &[String] {
+        &self.fallbacks
+    }
+
+    /// The names of all registered families
+    pub fn families(&self) -> impl Iterator<Item = &str> {
+        self.families.keys().map(String::as_str)
+    }
+
+    /// Gets the data of a family, falling back to the default font if it isn't registered
+    pub fn get(&self, family: &str) -> &[u8] {
+        self.families
+            .get(family)
+            .map_or(DEFAULT_FONT, FontData::bytes)
+    }
+
+    fn data(&self, family: &str) -> FontData {
+        self.families
+            .get(family)
+            .cloned()
+            .unwrap_or(FontData::Static(DEFAULT_FONT))
+    }
+
+    /// Loads a family at the specified size
+    ///
+    /// # Arguments
+    ///
+    /// * `ttf_context`: The context to load the font with
+    /// * `family`: The family name. Unregistered families fall back to the default font
+    /// * `size`: The font size, in points
+    ///
+    /// returns: Result<Font, String> An error if the font data can't be parsed
+    pub fn load<'r>(
+        &'r self,
+        ttf_context: &'static Sdl2TtfContext,
+        family: &str,
+        size: u16,
+    ) -> Result<Font<'static, 'r>, String> {
+        load_font(ttf_context, family, self.get(family), size)
+    }
+}
+
+impl Default for FontRegistry {
+    fn default() -> Self {
+        FontRegistry::new()
+    }
+}
+
+fn load_font<'r>(
+    ttf_context: &'static Sdl2TtfContext,
+    family: &str,
+    data: &'r [u8],
+    size: u16,
+) -> Result<Font<'static, 'r>, String> {
+    ttf_context
+        .load_font_from_rwops(RWops::from_bytes(data)?, size)
+        .map_err(|e| format!("Failed to load font `{}`: {}", family, e))
+}
+
+/// Identifies a loaded font
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct FontKey {
+    pub family: String,
+    pub size: u16,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+}
+
+impl FontKey {
+    fn sdl_style(&self) -> FontStyle {
+        let mut style = FontStyle::NORMAL;
+        if self.bold {
+            style |= FontStyle::BOLD;
+        }
+        if self.italic {
+            style |= FontStyle::ITALIC;
+        }
+        if self.underline {
+            style |= FontStyle::UNDERLINE;
+        }
+        if self.strikethrough {
+            style |= FontStyle::STRIKETHROUGH;
+        }
+        style
+    }
+}
+
+/// Describes how a control's text looks
+///
+/// Unspecified properties are taken from the theme's font and the control's current style.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TextStyle {
+    pub family: Option<String>,
+
+    /// The font size, in points
+    pub size: Option<u16>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub strikethrough: bool,
+    pub color: Option<Color>,
+    pub wrapping: TextWrapping,
+    pub trimming: TextTrimming,
+    pub alignment: TextAlignment,
+
+    /// The distance between the tops of consecutive lines, in pixels. Defaults to the font's recommended line spacing
+    pub line_height: Option<f32>,
+}
+
+impl TextStyle {
+    /// Gets the key of the font this style is displayed with
+    pub fn font_key(&self, theme: &Theme) -> FontKey {
+        FontKey {
+            family: self
+                .family
+                .clone()
+                .unwrap_or_else(|| theme.font.family.clone()),
+            size: self.size.unwrap_or(theme.font.size),
+            bold: self.bold,
+            italic: self.italic,
+            underline: self.underline,
+            strikethrough: self.strikethrough,
+        }
+    }
+}
+
+/// A font along with the data it reads its glyphs from
+struct CachedFont {
+    // Declared first, so it's dropped before its data
+    font: Font<'static, 'static>,
+    _data: FontData,
+}
+
+/// Caches fonts by family, size and style, so they're only loaded once
+///
+/// Families whose data can't be loaded are displayed with the default font instead.
+/// SDL_ttf is only initialized once the first font is loaded.
+pub struct FontCache {
+    registry: FontRegistry,
+    fonts: HashMap<FontKey, CachedFont>,
+}
+
+impl FontCache {
+    pub fn new(registry: FontRegistry) -> FontCache {
+        FontCache {
+            registry,
+            fonts: HashMap::new(),
+        }
+    }
+
+    /// Gets the font described by the key, loading it if it isn't cached yet
+    ///
+    /// returns: Result<&Font, String> An error if neither the family nor the default font can be loaded
+    pub fn get(&mut self, key: &FontKey) -> Result<&Font<'static, 'static>, String> {
+        if !self.fonts.contains_key(key) {
+            let mut font = self.load(&key.family, key.size).or_else(|e| {
+                if key.family == DEFAULT_FONT_FAMILY {
+                    Err(e)
+                } else {
+                    self.load(DEFAULT_FONT_FAMILY, key.size)
+                }
+            })?;
+            font.font.set_style(key.sdl_style());
+            self.fonts.insert(key.clone(), font);
+        }
+        Ok(&self.fonts[key].font)
+    }
+
+    fn load(&self, family: &str, size: u16) -> Result<CachedFont, String> {
+        let data = self.registry.data(family);
+        let font = load_font(ttf_context()?, family, data.bytes(), size)?;
+        // SAFETY: the font reads from the data's heap allocation, which doesn't move, and which the cached font
+        // keeps alive until after the font is dropped
+        let font =
+            unsafe { std::mem::transmute::<Font<'static, '_>, Font<'static, 'static>>(font) };
+        Ok(CachedFont { font, _data: data })
+    }
+
+    /// Gets the fonts consulted for a character, starting with the font described by the key
+    ///
+    /// Fallbacks share the key's size and style. The default font comes last.
+    pub fn fallback_chain(&self, key: &FontKey) -> Vec<FontKey> {
+        let mut chain = vec![key.clone()];
+        for family in self
+            .registry
+            .fallbacks()
+            .iter()
+            .map(String::as_str)
+            .chain([DEFAULT_FONT_FAMILY])
+        {
+            if chain.iter().all(|x| x.family != family) {
+                chain.push(FontKey {
+                    family: family.to_string(),
+                    ..key.clone()
+                });
+            }
+        }
+        chain
+    }
+}
+
+// Fonts which can't be loaded measure as empty, so text is left out rather than aborting the program
+impl TextMeasurer for FontCache {
+    fn has_glyph(&mut self, font: &FontKey, c: char) -> bool {
+        self.get(font).is_ok_and(|x| x.find_glyph(c).is_some())
+    }
+
+    fn font_for(&mut self, font: &FontKey, c: char) -> FontKey {
+        self.fallback_chain(font)
+            .into_iter()
+            .find(|x| self.has_glyph(x, c))
+            .unwrap_or_else(|| font.clone())
+    }
+
+    fn glyph_advance(&mut self, font: &FontKey, c: char) -> f32 {
+        self.get(font)
+            .ok()
+            .and_then(|x| x.find_glyph_metrics(c))
+            .map_or(0.0, |x| x.advance as f32)
+    }
+
+    fn measure(&mut self, font: &FontKey, text: &str) -> Point {
+        let size = self
+            .get(font)
+            .ok()
+            .and_then(|x| x.size_of(text).ok())
+            .unwrap_or((0, 0));
+        Point {
+            x: size.0 as f32,
+            y: size.1 as f32,
+        }
+    }
+
+    fn height(&mut self, font: &FontKey) -> f32 {
+        self.get(font).map_or(0.0, |x| x.height() as f32)
+    }
+
+    fn ascent(&mut self, font: &FontKey) -> f32 {
+        self.get(font).map_or(0.0, |x| x.ascent() as f32)
+    }
+
+    fn line_spacing(&mut self, font: &FontKey) -> f32 {
+        self.get(font)
+            .map_or(0.0, |x| x.recommended_line_spacing() as f32)
+    }
+}
@@ -1,3 +1,10 @@
 pub mod controls;
 pub mod core;
+pub mod fonts;
+pub mod overlay;
+pub mod render;
+pub mod skin;
+pub mod testing;
+pub mod text;
+pub mod theme;
 pub mod window;
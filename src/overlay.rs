@@ -0,0 +1,204 @@
+use crate::controls::control::{BaseControl, Control};
+use crate::controls::image::ImageCache;
+use crate::core::geo::{FlowDirection, Rect};
+use crate::core::messages::Message;
+use crate::fonts::{ttf_context, FontCache, FontRegistry};
+use crate::render::sdl::SdlRenderer;
+use crate::text::{TextCache, TextCacheStats, DEFAULT_TEXT_CACHE_CAPACITY};
+use crate::theme::Theme;
+use sdl2::event::Event;
+use sdl2::keyboard::Mod;
+use sdl2::render::{BlendMode, WindowCanvas};
+use std::time::Duration;
+
+pub struct OverlayBuilder {
+    content: Control,
+    theme: Theme,
+    fonts: FontRegistry,
+}
+
+impl OverlayBuilder {
+    pub fn new() -> OverlayBuilder {
+        OverlayBuilder {
+            content: Control::Stack {
+                base: BaseControl::default(),
+                orientation: Default::default(),
+            },
+            theme: Theme::default(),
+            fonts: FontRegistry::new(),
+        }
+    }
+    pub fn content(mut self, control: Control) -> OverlayBuilder {
+        self.content = control;
+        self
+    }
+    pub fn theme(mut self, theme: Theme) -> OverlayBuilder {
+        self.theme = theme;
+        self
+    }
+    pub fn fonts(mut self, fonts: FontRegistry) -> OverlayBuilder {
+        self.fonts = fonts;
+        self
+    }
+    /// Creates the overlay for a canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `canvas`: The canvas the overlay is drawn to. The overlay must be dropped before it
+    ///
+    /// returns: Result<Overlay, String> An error if SDL_ttf can't be initialized, or if a registered font can't be loaded
+    pub fn build(self, canvas: &WindowCanvas) -> Result<Overlay, String> {
+        let ttf_context = ttf_context()?;
+
+        // Fonts are loaded once up front, so malformed font data is reported here rather than while drawing
+        for family in self.fonts.families() {
+            self.fonts.load(ttf_context, family, self.theme.font.size)?;
+        }
+
+        Ok(Overlay {
+            image_cache: ImageCache::new(canvas.texture_creator()),
+            text_cache: TextCache::new(canvas.texture_creator(), DEFAULT_TEXT_CACHE_CAPACITY),
+            content: self.content,
+            theme: self.theme,
+            fonts: FontCache::new(self.fonts),
+            modifiers: Mod::NOMOD,
+            layout_viewport: None,
+        })
+    }
+}
+
+impl Default for OverlayBuilder {
+    fn default() -> Self {
+        OverlayBuilder::new()
+    }
+}
+
+/// A control tree driven by an application's own loop, and drawn over what it draws
+///
+/// Each frame, the application feeds its SDL events to `process_event`, then calls `update`, `layout` and `render`.
+/// To draw into a texture instead, render inside the canvas' `with_texture_canvas`.
+pub struct Overlay {
+    image_cache: ImageCache,
+    text_cache: TextCache,
+    content: Control,
+    theme: Theme,
+    fonts: FontCache,
+    // Mouse button events don't carry the modifier keys, so they're tracked from key events
+    modifiers: Mod,
+    // The area the content was last laid out in, so layout is skipped while neither it nor the content changes
+    layout_viewport: Option<Rect>,
+}
+
+impl Overlay {
+    /// Routes an SDL event to the content, if controls handle it
+    ///
+    /// Quitting is left to the application.
+    ///
+    /// returns: bool Whether the content changed how it looks, and so should be drawn again
+    pub fn process_event(&mut self, event: &Event) -> bool {
+        if let Event::KeyDown { keymod, .. } | Event::KeyUp { keymod, .. } = event {
+            self.modifiers = *keymod;
+        }
+        Message::from_event(event, self.modifiers).is_some_and(|x| self.process_message(x))
+    }
+
+    /// Routes a message to the content, such as one synthesized from the application's own input
+    ///
+    /// returns: bool Whether the content changed how it looks, and so should be drawn again
+    pub fn process_message(&mut self, message: Message) -> bool {
+        self.content.process_message(message)
+    }
+
+    /// Advances time-based state, such as animations, by the elapsed frame time
+    pub fn update(&mut self, delta: Duration) {
+        self.content.update(delta);
+    }
+
+    /// Lays out the content inside a region, if it changed or the region differs from the last layout's
+    ///
+    /// # Arguments
+    ///
+    /// * `viewport`: The region the content fills, usually the whole canvas
+    ///
+    /// returns: bool Whether the content was laid out, in which case it should be drawn again
+    pub fn layout(&mut self, viewport: Rect) -> bool {
+        if !self.content.needs_layout() && self.layout_viewport == Some(viewport) {
+            return false;
+        }
+        self.content.do_layout(
+            viewport,
+            FlowDirection::default(),
+            &mut self.fonts,
+            &self.theme,
+        );
+        self.layout_viewport = Some(viewport);
+        true
+    }
+
+    /// Draws the content with the bounds computed by the last layout
+    ///
+    /// Nothing is cleared, so the content is drawn over the canvas' current contents, and the frame isn't presented.
+    /// The canvas' draw color, blend mode and clip area are restored afterwards.
+    ///
+    /// # Arguments
+    ///
+    /// * `canvas`: The canvas the overlay was created for, or a texture canvas created by it
+    pub fn render(&mut self, canvas: &mut WindowCanvas) {
+        let draw_color = canvas.draw_color();
+        let blend_mode = canvas.blend_mode();
+        let clip_rect = canvas.clip_rect();
+
+        canvas.set_blend_mode(BlendMode::Blend);
+        self.content.render(
+            &mut SdlRenderer::new(
+                canvas,
+                &mut self.image_cache,
+                &mut self.fonts,
+                &mut self.text_cache,
+            ),
+            &self.theme,
+        );
+        self.image_cache.collect();
+
+        canvas.set_draw_color(draw_color);
+        canvas.set_blend_mode(blend_mode);
+        canvas.set_clip_rect(clip_rect);
+    }
+
+    /// The root of the overlay's control tree
+    pub fn content(&self) -> &Control {
+        &self.content
+    }
+
+    /// Gets mutable access to the tree
+    ///
+    /// Changes made through the tree's mutation methods, such as `Control::modify`, are laid out by the next `layout`.
+    pub fn content_mut(&mut self) -> &mut Control {
+        &mut self.content
+    }
+
+    pub fn set_content(&mut self, control: Control) {
+        self.content = control;
+        // The content may have been laid out elsewhere, with another size or theme
+        self.content.invalidate_layout();
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+        self.content.invalidate_layout();
+    }
+
+    /// Gets the counters of the cache which holds rendered text, for profiling
+    pub fn text_cache_stats(&self) -> TextCacheStats {
+        self.text_cache.stats()
+    }
+
+    /// Changes the number of rendered strings kept between frames
+    pub fn set_text_cache_capacity(&mut self, capacity: usize) {
+        self.text_cache.set_capacity(capacity);
+    }
+}
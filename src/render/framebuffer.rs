@@ -0,0 +1,317 @@
+use crate::controls::image::ImageData;
+use sdl2::pixels::Color;
+use std::fs;
+use std::path::Path;
+
+/// An RGBA image kept in memory, which headless renderers draw to
+#[derive(Clone, PartialEq, Debug)]
+pub struct Framebuffer {
+    width: u32,
+    height: u32,
+
+    /// The pixels, stored row by row as 4 bytes per pixel in R, G, B, A order
+    pixels: Vec<u8>,
+}
+
+impl Framebuffer {
+    /// Creates a framebuffer whose pixels are all transparent black
+    pub fn new(width: u32, height: u32) -> Framebuffer {
+        Framebuffer {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        }
+    }
+
+    /// Copies the pixels of an image
+    pub fn from_image(image: &ImageData) -> Framebuffer {
+        Framebuffer {
+            width: image.width,
+            height: image.height,
+            pixels: image.pixels.to_vec(),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The pixels, stored row by row as 4 bytes per pixel in R, G, B, A order
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+
+    fn offset(&self, x: u32, y: u32) -> usize {
+        (y as usize * self.width as usize + x as usize) * 4
+    }
+
+    /// Gets the color of a pixel
+    ///
+    /// # Panics
+    ///
+    /// If the pixel lies outside the framebuffer
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        assert!(x < self.width && y < self.height, "Pixel out of bounds");
+        let i = self.offset(x, y);
+        Color::RGBA(
+            self.pixels[i],
+            self.pixels[i + 1],
+            self.pixels[i + 2],
+            self.pixels[i + 3],
+        )
+    }
+
+    /// Replaces every pixel with a color
+    pub fn clear(&mut self, color: Color) {
+        for pixel in self.pixels.chunks_exact_mut(4) {
+            pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+        }
+    }
+
+    /// Blends a color over a pixel by its alpha, the same way SDL's blend mode does
+    ///
+    /// Pixels outside the framebuffer are ignored.
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: Color) {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return;
+        }
+        let i = self.offset(x as u32, y as u32);
+        let alpha = color.a as u32;
+        let blend = |source: u8, destination: u8| {
+            ((source as u32 * alpha + destination as u32 * (255 - alpha)) / 255) as u8
+        };
+        let pixel = &mut self.pixels[i..i + 4];
+        pixel[0] = blend(color.r, pixel[0]);
+        pixel[1] = blend(color.g, pixel[1]);
+        pixel[2] = blend(color.b, pixel[2]);
+        pixel[3] = (alpha + pixel[3] as u32 * (255 - alpha) / 255) as u8;
+    }
+
+    /// Copies the pixels into an image, so they can be drawn by a renderer
+    pub fn to_image(&self) -> ImageData {
+        ImageData::from_rgba(self.width, self.height, self.pixels.clone()).unwrap()
+    }
+
+    /// Encodes the pixels as a PNG file
+    ///
+    /// The image data is stored without compression, trading file size for a small encoder.
+    pub fn encode_png(&self) -> Vec<u8> {
+        let mut header = vec![];
+        header.extend_from_slice(&self.width.to_be_bytes());
+        header.extend_from_slice(&self.height.to_be_bytes());
+        // 8 bits per channel, RGBA, default compression, filtering and no interlacing
+        header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+        // Every row starts with its filter type, which is always none
+        let row_length = self.width as usize * 4;
+        let mut scanlines = Vec::with_capacity((row_length + 1) * self.height as usize);
+        for row in self.pixels.chunks_exact(row_length.max(1)) {
+            scanlines.push(0);
+            scanlines.extend_from_slice(row);
+        }
+
+        let mut png = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'];
+        write_png_chunk(&mut png, b"IHDR", &header);
+        write_png_chunk(&mut png, b"IDAT", &zlib_stored(&scanlines));
+        write_png_chunk(&mut png, b"IEND", &[]);
+        png
+    }
+
+    /// Encodes the pixels as a 32 bits per pixel BMP file with an alpha channel
+    pub fn encode_bmp(&self) -> Vec<u8> {
+        const FILE_HEADER_SIZE: u32 = 14;
+        const INFO_HEADER_SIZE: u32 = 108;
+        let data_size = self.width * self.height * 4;
+
+        let mut bmp = vec![];
+        bmp.extend_from_slice(b"BM");
+        bmp.extend_from_slice(&(FILE_HEADER_SIZE + INFO_HEADER_SIZE + data_size).to_le_bytes());
+        bmp.extend_from_slice(&[0; 4]);
+        bmp.extend_from_slice(&(FILE_HEADER_SIZE + INFO_HEADER_SIZE).to_le_bytes());
+
+        // BITMAPV4HEADER, whose channel masks describe the BGRA pixels
+        bmp.extend_from_slice(&INFO_HEADER_SIZE.to_le_bytes());
+        bmp.extend_from_slice(&(self.width as i32).to_le_bytes());
+        bmp.extend_from_slice(&(self.height as i32).to_le_bytes());
+        bmp.extend_from_slice(&1u16.to_le_bytes());
+        bmp.extend_from_slice(&32u16.to_le_bytes());
+        // BI_BITFIELDS
+        bmp.extend_from_slice(&3u32.to_le_bytes());
+        bmp.extend_from_slice(&data_size.to_le_bytes());
+        // 72 DPI
+        bmp.extend_from_slice(&2835i32.to_le_bytes());
+        bmp.extend_from_slice(&2835i32.to_le_bytes());
+        bmp.extend_from_slice(&[0; 8]);
+        for mask in [0x00FF0000u32, 0x0000FF00, 0x000000FF, 0xFF000000] {
+            bmp.extend_from_slice(&mask.to_le_bytes());
+        }
+        // LCS_WINDOWS_COLOR_SPACE, followed by the unused endpoints and gamma
+        bmp.extend_from_slice(b"Win ");
+        bmp.extend_from_slice(&[0; 48]);
+
+        // Rows are stored bottom-up
+        for y in (0..self.height).rev() {
+            for x in 0..self.width {
+                let i = self.offset(x, y);
+                let pixel = &self.pixels[i..i + 4];
+                bmp.extend_from_slice(&[pixel[2], pixel[1], pixel[0], pixel[3]]);
+            }
+        }
+        bmp
+    }
+
+    /// Decodes an uncompressed BMP file with 24 or 32 bits per pixel, such as the ones written by `encode_bmp`
+    ///
+    /// 24 bit images are opaque. 32 bit images use their channel masks if they have any, and are stored as BGRA otherwise.
+    ///
+    /// returns: Result<Framebuffer, String> An error if the data isn't a BMP file in a supported format
+    pub fn decode_bmp(data: &[u8]) -> Result<Framebuffer, String> {
+        let u16_at = |i: usize| data.get(i..i + 2).map(|x| u16::from_le_bytes([x[0], x[1]]));
+        let u32_at = |i: usize| {
+            data.get(i..i + 4)
+                .map(|x| u32::from_le_bytes([x[0], x[1], x[2], x[3]]))
+        };
+        let truncated = || "Truncated BMP file".to_string();
+
+        if !data.starts_with(b"BM") {
+            return Err("Not a BMP file".to_string());
+        }
+        let data_offset = u32_at(10).ok_or_else(truncated)? as usize;
+        let header_size = u32_at(14).ok_or_else(truncated)?;
+        let width = u32_at(18).ok_or_else(truncated)? as i32;
+        let height = u32_at(22).ok_or_else(truncated)? as i32;
+        let bits = u16_at(28).ok_or_else(truncated)?;
+        let compression = u32_at(30).ok_or_else(truncated)?;
+        if header_size < 40 || width < 0 {
+            return Err("Unsupported BMP header".to_string());
+        }
+
+        // Bitfields follow the 40 byte header, which larger headers include
+        let masks = match (compression, bits) {
+            (0, 24 | 32) => [0x00FF0000, 0x0000FF00, 0x000000FF, 0],
+            (3, 32) => {
+                let mask = |i: usize| u32_at(54 + i * 4).ok_or_else(truncated);
+                let alpha = if header_size >= 56 { mask(3)? } else { 0 };
+                [mask(0)?, mask(1)?, mask(2)?, alpha]
+            }
+            _ => {
+                return Err(format!(
+                    "Unsupported BMP format: {} bits per pixel with compression {}",
+                    bits, compression
+                ))
+            }
+        };
+        let channel = |pixel: u32, mask: u32| {
+            if mask == 0 {
+                return None;
+            }
+            let value = (pixel & mask) >> mask.trailing_zeros();
+            let max = mask >> mask.trailing_zeros();
+            Some((value * 255 / max) as u8)
+        };
+
+        // Rows are padded to whole words, and stored bottom-up unless the height is negative
+        let (width, top_down, height) = (width as u32, height < 0, height.unsigned_abs());
+        let bytes_per_pixel = bits as usize / 8;
+        let stride = (width as usize * bytes_per_pixel).div_ceil(4) * 4;
+        let mut framebuffer = Framebuffer::new(width, height);
+        for y in 0..height {
+            let row = if top_down { y } else { height - 1 - y } as usize;
+            let start = data_offset + row * stride;
+            let row = data
+                .get(start..start + width as usize * bytes_per_pixel)
+                .ok_or_else(truncated)?;
+            for (x, pixel) in row.chunks_exact(bytes_per_pixel).enumerate() {
+                let pixel = pixel
+                    .iter()
+                    .rev()
+                    .fold(0u32, |value, byte| value << 8 | *byte as u32);
+                let i = framebuffer.offset(x as u32, y);
+                framebuffer.pixels[i..i + 4].copy_from_slice(&[
+                    channel(pixel, masks[0]).unwrap_or(0),
+                    channel(pixel, masks[1]).unwrap_or(0),
+                    channel(pixel, masks[2]).unwrap_or(0),
+                    channel(pixel, masks[3]).unwrap_or(255),
+                ]);
+            }
+        }
+        Ok(framebuffer)
+    }
+
+    /// Loads a BMP file in one of the formats supported by `decode_bmp`
+    pub fn load_bmp<P: AsRef<Path>>(path: P) -> Result<Framebuffer, String> {
+        let data = fs::read(path.as_ref())
+            .map_err(|e| format!("Failed to read {}: {}", path.as_ref().display(), e))?;
+        Framebuffer::decode_bmp(&data)
+            .map_err(|e| format!("Failed to decode {}: {}", path.as_ref().display(), e))
+    }
+
+    /// Saves the pixels as a PNG file
+    pub fn save_png<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path.as_ref(), self.encode_png())
+            .map_err(|e| format!("Failed to write {}: {}", path.as_ref().display(), e))
+    }
+
+    /// Saves the pixels as a BMP file
+    pub fn save_bmp<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        fs::write(path.as_ref(), self.encode_bmp())
+            .map_err(|e| format!("Failed to write {}: {}", path.as_ref().display(), e))
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFFFFFFu32;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB88320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+    for &byte in data {
+        a = (a + byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+fn write_png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Wraps data in a zlib stream made of uncompressed deflate blocks
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 65535;
+    let mut stream = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = if data.is_empty() {
+        vec![&[]]
+    } else {
+        data.chunks(MAX_BLOCK).collect()
+    };
+    for (i, block) in blocks.iter().enumerate() {
+        stream.push((i == blocks.len() - 1) as u8);
+        let length = block.len() as u16;
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
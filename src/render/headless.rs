@@ -0,0 +1,317 @@
+use crate::controls::control::Control;
+use crate::controls::image::ImageData;
+use crate::core::geo::{FlowDirection, Point, Rect};
+use crate::fonts::{ttf_context, FontCache, FontKey, FontRegistry};
+use crate::render::framebuffer::Framebuffer;
+use crate::render::{ClipStack, Renderer, TextMeasurer};
+use crate::theme::Theme;
+use sdl2::pixels::Color;
+
+/// Draws to an in-memory framebuffer without opening a window
+///
+/// Only SDL_ttf is initialized, so rendering works on machines without a display.
+/// Primitives are rasterized the same way SDL's renderer rasterizes them, with pixel coordinates truncated and colors alpha blended.
+pub struct HeadlessRenderer {
+    framebuffer: Framebuffer,
+    fonts: FontCache,
+    clip_stack: ClipStack,
+}
+
+/// A rect snapped to whole pixels, as `(left, top, right, bottom)` with exclusive right and bottom edges
+type PixelRect = (i32, i32, i32, i32);
+
+fn pixel_rect(rect: Rect) -> PixelRect {
+    let (x, y) = (rect.x as i32, rect.y as i32);
+    (x, y, x + rect.w.max(0.0) as i32, y + rect.h.max(0.0) as i32)
+}
+
+/// Clips a line to the pixels of a pixel rect with the Cohen-Sutherland algorithm, the way SDL clips lines
+///
+/// returns: Option<((i32, i32), (i32, i32))> The end points of the visible part of the line, or `None` if none of it is visible
+fn clip_line(
+    from: (i32, i32),
+    to: (i32, i32),
+    (left, top, right, bottom): PixelRect,
+) -> Option<((i32, i32), (i32, i32))> {
+    const LEFT: u8 = 1;
+    const RIGHT: u8 = 2;
+    const TOP: u8 = 4;
+    const BOTTOM: u8 = 8;
+    if right <= left || bottom <= top {
+        return None;
+    }
+    // The last pixels inside the rect, as the line's end points are drawn too. Wide integers keep the
+    // interpolation from overflowing for far away points
+    let (right, bottom) = (right as i128 - 1, bottom as i128 - 1);
+    let (left, top) = (left as i128, top as i128);
+    let outcode = |(x, y): (i128, i128)| {
+        let mut code = 0;
+        if x < left {
+            code |= LEFT;
+        } else if x > right {
+            code |= RIGHT;
+        }
+        if y < top {
+            code |= TOP;
+        } else if y > bottom {
+            code |= BOTTOM;
+        }
+        code
+    };
+
+    let (mut a, mut b) = (
+        (from.0 as i128, from.1 as i128),
+        (to.0 as i128, to.1 as i128),
+    );
+    loop {
+        let (code_a, code_b) = (outcode(a), outcode(b));
+        if code_a == 0 && code_b == 0 {
+            return Some(((a.0 as i32, a.1 as i32), (b.0 as i32, b.1 as i32)));
+        }
+        if code_a & code_b != 0 {
+            return None;
+        }
+        let code = if code_a != 0 { code_a } else { code_b };
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let point = if code & TOP != 0 {
+            (a.0 + dx * (top - a.1) / dy, top)
+        } else if code & BOTTOM != 0 {
+            (a.0 + dx * (bottom - a.1) / dy, bottom)
+        } else if code & LEFT != 0 {
+            (left, a.1 + dy * (left - a.0) / dx)
+        } else {
+            (right, a.1 + dy * (right - a.0) / dx)
+        };
+        if code == code_a {
+            a = point;
+        } else {
+            b = point;
+        }
+    }
+}
+
+impl HeadlessRenderer {
+    /// Creates a renderer drawing to a transparent framebuffer of the specified size
+    ///
+    /// # Arguments
+    ///
+    /// * `width`: The framebuffer's width in pixels
+    /// * `height`: The framebuffer's height in pixels
+    /// * `fonts`: The font families text is drawn with
+    ///
+    /// returns: Result<HeadlessRenderer, String> An error if SDL_ttf can't be initialized
+    pub fn new(width: u32, height: u32, fonts: FontRegistry) -> Result<HeadlessRenderer, String> {
+        ttf_context()?;
+        Ok(HeadlessRenderer {
+            framebuffer: Framebuffer::new(width, height),
+            fonts: FontCache::new(fonts),
+            clip_stack: ClipStack::new(),
+        })
+    }
+
+    pub fn framebuffer(&self) -> &Framebuffer {
+        &self.framebuffer
+    }
+
+    pub fn framebuffer_mut(&mut self) -> &mut Framebuffer {
+        &mut self.framebuffer
+    }
+
+    pub fn into_framebuffer(self) -> Framebuffer {
+        self.framebuffer
+    }
+
+    /// Lays out a control tree to fill the framebuffer, then draws it over the theme's window background
+    pub fn render_tree(&mut self, content: &mut Control, theme: &Theme) {
+        content.do_layout(self.viewport(), FlowDirection::default(), self, theme);
+        self.framebuffer.clear(theme.palette.window_background);
+        content.render(self, theme);
+    }
+
+    /// The pixels which can currently be drawn to
+    fn drawable(&self) -> PixelRect {
+        let (mut left, mut top, mut right, mut bottom) = (
+            0,
+            0,
+            self.framebuffer.width() as i32,
+            self.framebuffer.height() as i32,
+        );
+        if let Some(clip) = self.clip_stack.current() {
+            let clip = pixel_rect(clip);
+            left = left.max(clip.0);
+            top = top.max(clip.1);
+            right = right.min(clip.2);
+            bottom = bottom.min(clip.3);
+        }
+        (left, top, right, bottom)
+    }
+
+    /// Blends a color over every drawable pixel of a pixel rect
+    fn fill_pixels(&mut self, (left, top, right, bottom): PixelRect, color: Color) {
+        let drawable = self.drawable();
+        for y in top.max(drawable.1)..bottom.min(drawable.3) {
+            for x in left.max(drawable.0)..right.min(drawable.2) {
+                self.framebuffer.blend_pixel(x, y, color);
+            }
+        }
+    }
+
+    /// Copies a region of an image to a pixel rect with nearest neighbour sampling, blending it by its alpha
+    fn blit(&mut self, image: &ImageData, source: Rect, destination: PixelRect) {
+        let (left, top, right, bottom) = destination;
+        if right <= left || bottom <= top || image.width == 0 || image.height == 0 {
+            return;
+        }
+        let scale_x = source.w / (right - left) as f32;
+        let scale_y = source.h / (bottom - top) as f32;
+        let drawable = self.drawable();
+        for y in top.max(drawable.1)..bottom.min(drawable.3) {
+            let source_y = (source.y + (y - top) as f32 * scale_y + scale_y / 2.0) as u32;
+            let source_y = source_y.min(image.height - 1);
+            for x in left.max(drawable.0)..right.min(drawable.2) {
+                let source_x = (source.x + (x - left) as f32 * scale_x + scale_x / 2.0) as u32;
+                let i = (source_y as usize * image.width as usize
+                    + source_x.min(image.width - 1) as usize)
+                    * 4;
+                let pixel = &image.pixels[i..i + 4];
+                self.framebuffer.blend_pixel(
+                    x,
+                    y,
+                    Color::RGBA(pixel[0], pixel[1], pixel[2], pixel[3]),
+                );
+            }
+        }
+    }
+}
+
+impl TextMeasurer for HeadlessRenderer {
+    fn has_glyph(&mut self, font: &FontKey, c: char) -> bool {
+        self.fonts.has_glyph(font, c)
+    }
+
+    fn font_for(&mut self, font: &FontKey, c: char) -> FontKey {
+        self.fonts.font_for(font, c)
+    }
+
+    fn glyph_advance(&mut self, font: &FontKey, c: char) -> f32 {
+        self.fonts.glyph_advance(font, c)
+    }
+
+    fn measure(&mut self, font: &FontKey, text: &str) -> Point {
+        self.fonts.measure(font, text)
+    }
+
+    fn height(&mut self, font: &FontKey) -> f32 {
+        self.fonts.height(font)
+    }
+
+    fn ascent(&mut self, font: &FontKey) -> f32 {
+        self.fonts.ascent(font)
+    }
+
+    fn line_spacing(&mut self, font: &FontKey) -> f32 {
+        self.fonts.line_spacing(font)
+    }
+}
+
+impl Renderer for HeadlessRenderer {
+    fn viewport(&self) -> Rect {
+        Rect::new(
+            0.0,
+            0.0,
+            self.framebuffer.width() as f32,
+            self.framebuffer.height() as f32,
+        )
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.fill_pixels(pixel_rect(rect), color);
+    }
+
+    fn stroke_rect(&mut self, rect: Rect, color: Color) {
+        let (left, top, right, bottom) = pixel_rect(rect);
+        if right <= left || bottom <= top {
+            return;
+        }
+        self.fill_pixels((left, top, right, top + 1), color);
+        if bottom - top > 1 {
+            self.fill_pixels((left, bottom - 1, right, bottom), color);
+        }
+        self.fill_pixels((left, top + 1, left + 1, bottom - 1), color);
+        if right - left > 1 {
+            self.fill_pixels((right - 1, top + 1, right, bottom - 1), color);
+        }
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, color: Color) {
+        // The line is clipped first, so lines reaching far outside the framebuffer don't step through every pixel
+        let Some(((mut x, mut y), (end_x, end_y))) = clip_line(
+            (from.x as i32, from.y as i32),
+            (to.x as i32, to.y as i32),
+            self.drawable(),
+        ) else {
+            return;
+        };
+
+        // Bresenham's algorithm, including both end points
+        let dx = (end_x - x).abs();
+        let dy = -(end_y - y).abs();
+        let step_x = if x < end_x { 1 } else { -1 };
+        let step_y = if y < end_y { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            self.framebuffer.blend_pixel(x, y, color);
+            if x == end_x && y == end_y {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, position: Point, font: &FontKey, color: Color) {
+        if text.is_empty() {
+            return;
+        }
+        // Text which can't be rendered is left out, as it measures as empty
+        let Ok(surface) = self
+            .fonts
+            .get(font)
+            .and_then(|x| x.render(text).blended(color).map_err(|e| e.to_string()))
+        else {
+            return;
+        };
+        let Ok(image) = ImageData::from_surface(&surface) else {
+            return;
+        };
+        let (x, y) = (position.x as i32, position.y as i32);
+        self.blit(
+            &image,
+            Rect::new(0.0, 0.0, image.width as f32, image.height as f32),
+            (x, y, x + image.width as i32, y + image.height as i32),
+        );
+    }
+
+    fn draw_image(&mut self, image: &ImageData, source: Rect, destination: Rect) {
+        self.blit(image, source, pixel_rect(destination));
+    }
+
+    fn clip(&self) -> Option<Rect> {
+        self.clip_stack.current()
+    }
+
+    fn push_clip(&mut self, rect: Rect) {
+        self.clip_stack.push(rect);
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+}
@@ -0,0 +1,100 @@
+use crate::controls::image::ImageData;
+use crate::core::geo::{Point, Rect};
+use crate::fonts::FontKey;
+use sdl2::pixels::Color;
+
+pub mod framebuffer;
+pub mod headless;
+pub mod sdl;
+
+/// Measures text for layout, independently of how it's drawn
+pub trait TextMeasurer {
+    /// Whether the font described by the key contains a glyph for the character
+    fn has_glyph(&mut self, font: &FontKey, c: char) -> bool;
+
+    /// Gets the first font in the key's fallback chain which contains a glyph for the character
+    ///
+    /// returns: FontKey The key itself if no font contains the glyph
+    fn font_for(&mut self, font: &FontKey, c: char) -> FontKey;
+
+    /// Gets the horizontal advance of a character's glyph, without kerning
+    fn glyph_advance(&mut self, font: &FontKey, c: char) -> f32;
+
+    /// Measures the size of a string, as displayed by the font described by the key
+    fn measure(&mut self, font: &FontKey, text: &str) -> Point;
+
+    /// Gets the height of a line of the font described by the key
+    fn height(&mut self, font: &FontKey) -> f32;
+
+    /// Gets the distance from the top of a line to the baseline of the font described by the key
+    fn ascent(&mut self, font: &FontKey) -> f32;
+
+    /// Gets the recommended distance between the tops of consecutive lines of the font described by the key
+    fn line_spacing(&mut self, font: &FontKey) -> f32;
+}
+
+/// Draws primitives for a control tree
+///
+/// Renderers measure text as well, so text is drawn with the same metrics it was laid out with.
+pub trait Renderer: TextMeasurer {
+    /// The area being drawn to
+    fn viewport(&self) -> Rect;
+
+    fn fill_rect(&mut self, rect: Rect, color: Color);
+
+    /// Outlines a rect with a one pixel stroke lying inside it
+    fn stroke_rect(&mut self, rect: Rect, color: Color);
+
+    /// Draws a one pixel wide line, including both of its end points
+    fn draw_line(&mut self, from: Point, to: Point, color: Color);
+
+    /// Draws a string with a single font, with its top-left corner at the specified position
+    ///
+    /// The string is drawn as is, so it should already be shaped and in visual order.
+    fn draw_text(&mut self, text: &str, position: Point, font: &FontKey, color: Color);
+
+    /// Draws a region of an image, stretched to the destination
+    fn draw_image(&mut self, image: &ImageData, source: Rect, destination: Rect);
+
+    /// The area drawing is currently restricted to, or `None` if it isn't clipped
+    fn clip(&self) -> Option<Rect>;
+
+    /// Restricts drawing to the intersection of a rect and the current clip area
+    ///
+    /// Every `push_clip` must be matched by a `pop_clip` once the clipped content is drawn.
+    fn push_clip(&mut self, rect: Rect);
+
+    /// Restores the clip area which was active before the matching `push_clip`
+    fn pop_clip(&mut self);
+}
+
+/// Tracks nested clip rectangles for a renderer, intersecting each with the ones below it
+#[derive(Clone, Debug, Default)]
+pub struct ClipStack {
+    rects: Vec<Rect>,
+}
+
+impl ClipStack {
+    pub fn new() -> ClipStack {
+        ClipStack { rects: vec![] }
+    }
+
+    /// The area drawing is currently restricted to, or `None` if it isn't clipped
+    pub fn current(&self) -> Option<Rect> {
+        self.rects.last().copied()
+    }
+
+    /// Pushes the intersection of a rect and the current clip area
+    pub fn push(&mut self, rect: Rect) {
+        let rect = match self.current() {
+            Some(current) => current.intersect(rect),
+            None => rect,
+        };
+        self.rects.push(rect);
+    }
+
+    /// Pops the most recently pushed clip area
+    pub fn pop(&mut self) {
+        self.rects.pop();
+    }
+}
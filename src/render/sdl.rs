@@ -0,0 +1,157 @@
+use crate::controls::image::{ImageCache, ImageData};
+use crate::core::geo::{Point, Rect};
+use crate::fonts::{FontCache, FontKey};
+use crate::render::{ClipStack, Renderer, TextMeasurer};
+use crate::text::TextCache;
+use sdl2::pixels::Color;
+use sdl2::render::WindowCanvas;
+
+/// Draws to a window's canvas through SDL's renderer
+///
+/// Textures for images and text are kept in the caches between frames, while clipping only lasts as long as the renderer.
+pub struct SdlRenderer<'a> {
+    canvas: &'a mut WindowCanvas,
+    image_cache: &'a mut ImageCache,
+    fonts: &'a mut FontCache,
+    text_cache: &'a mut TextCache,
+    clip_stack: ClipStack,
+}
+
+impl<'a> SdlRenderer<'a> {
+    /// Creates a renderer for a canvas
+    ///
+    /// # Arguments
+    ///
+    /// * `canvas`: The canvas to draw to
+    /// * `image_cache`: The cache of image textures, which must have been created from the canvas' texture creator
+    /// * `fonts`: The fonts text is measured and drawn with
+    /// * `text_cache`: The cache of text textures, which must have been created from the canvas' texture creator
+    pub fn new(
+        canvas: &'a mut WindowCanvas,
+        image_cache: &'a mut ImageCache,
+        fonts: &'a mut FontCache,
+        text_cache: &'a mut TextCache,
+    ) -> SdlRenderer<'a> {
+        SdlRenderer {
+            canvas,
+            image_cache,
+            fonts,
+            text_cache,
+            clip_stack: ClipStack::new(),
+        }
+    }
+
+    fn apply_clip(&mut self) {
+        match self.clip_stack.current() {
+            // SDL rects are at least one pixel wide, so empty areas are moved off the canvas instead
+            Some(rect) if rect.w < 1.0 || rect.h < 1.0 => {
+                self.canvas
+                    .set_clip_rect(Rect::new(-1.0, -1.0, 1.0, 1.0).to_sdl());
+            }
+            Some(rect) => self.canvas.set_clip_rect(rect.to_sdl()),
+            None => self.canvas.set_clip_rect(None),
+        }
+    }
+}
+
+impl Drop for SdlRenderer<'_> {
+    fn drop(&mut self) {
+        // Unbalanced clips mustn't leak into whatever is drawn to the canvas next
+        if self.clip_stack.current().is_some() {
+            self.canvas.set_clip_rect(None);
+        }
+    }
+}
+
+impl TextMeasurer for SdlRenderer<'_> {
+    fn has_glyph(&mut self, font: &FontKey, c: char) -> bool {
+        self.fonts.has_glyph(font, c)
+    }
+
+    fn font_for(&mut self, font: &FontKey, c: char) -> FontKey {
+        self.fonts.font_for(font, c)
+    }
+
+    fn glyph_advance(&mut self, font: &FontKey, c: char) -> f32 {
+        self.fonts.glyph_advance(font, c)
+    }
+
+    fn measure(&mut self, font: &FontKey, text: &str) -> Point {
+        self.fonts.measure(font, text)
+    }
+
+    fn height(&mut self, font: &FontKey) -> f32 {
+        self.fonts.height(font)
+    }
+
+    fn ascent(&mut self, font: &FontKey) -> f32 {
+        self.fonts.ascent(font)
+    }
+
+    fn line_spacing(&mut self, font: &FontKey) -> f32 {
+        self.fonts.line_spacing(font)
+    }
+}
+
+impl Renderer for SdlRenderer<'_> {
+    fn viewport(&self) -> Rect {
+        let viewport = self.canvas.viewport();
+        Rect::new(
+            viewport.x() as f32,
+            viewport.y() as f32,
+            viewport.width() as f32,
+            viewport.height() as f32,
+        )
+    }
+
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.canvas.set_draw_color(color);
+        self.canvas.fill_rect(rect.to_sdl()).unwrap();
+    }
+
+    fn stroke_rect(&mut self, rect: Rect, color: Color) {
+        self.canvas.set_draw_color(color);
+        self.canvas.draw_rect(rect.to_sdl()).unwrap();
+    }
+
+    fn draw_line(&mut self, from: Point, to: Point, color: Color) {
+        self.canvas.set_draw_color(color);
+        self.canvas.draw_line(from.to_sdl(), to.to_sdl()).unwrap();
+    }
+
+    fn draw_text(&mut self, text: &str, position: Point, font: &FontKey, color: Color) {
+        // Text which can't be rendered is left out, as it measures as empty
+        if let Ok(Some((texture, size))) = self.text_cache.get(self.fonts, font, color, text) {
+            let destination = Rect::new(position.x, position.y, size.x, size.y);
+            let _ = self.canvas.copy(texture, None, destination.to_sdl());
+        }
+    }
+
+    fn draw_image(&mut self, image: &ImageData, source: Rect, destination: Rect) {
+        // Images which can't be turned into textures, such as ones larger than the renderer supports, are left out
+        let result = self.image_cache.get(image).and_then(|texture| {
+            self.canvas
+                .copy(texture, source.to_sdl(), destination.to_sdl())
+        });
+        if let Err(e) = result {
+            eprintln!(
+                "Failed to draw a {}x{} image: {}",
+                image.width, image.height, e
+            );
+        }
+    }
+
+    fn clip(&self) -> Option<Rect> {
+        self.clip_stack.current()
+    }
+
+    fn push_clip(&mut self, rect: Rect) {
+        self.clip_stack.push(rect);
+        self.apply_clip();
+    }
+
+    fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+        self.apply_clip();
+    }
+}
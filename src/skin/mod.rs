@@ -0,0 +1,201 @@
+use crate::controls::image::ImageData;
+use crate::core::geo::{Rect, Thickness};
+use crate::render::Renderer;
+use crate::theme::VisualState;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// A region of a skin's atlas, which is stretched to a control's bounds via nine-slice scaling
+///
+/// The corners keep their size, the edges stretch along one axis and the center stretches along both.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SkinPart {
+    /// The region inside the atlas, in pixels
+    pub source: Rect,
+
+    /// The distances from the region's edges at which it's sliced
+    pub margins: Thickness,
+}
+
+/// A sprite atlas along with the parts controls are drawn from
+///
+/// Parts are keyed by a theme style name (e.g.: `button`) and a visual state.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Skin {
+    pub atlas: ImageData,
+    pub parts: HashMap<(String, VisualState), SkinPart>,
+}
+
+fn parse_state(name: &str) -> Option<VisualState> {
+    match name {
+        "normal" => Some(VisualState::Normal),
+        "hover" => Some(VisualState::Hover),
+        "pressed" => Some(VisualState::Pressed),
+        "disabled" => Some(VisualState::Disabled),
+        "focused" => Some(VisualState::Focused),
+        _ => None,
+    }
+}
+
+impl Skin {
+    /// Loads a skin from a BMP atlas and a description file
+    ///
+    /// # Arguments
+    ///
+    /// * `atlas_path`: The path to the atlas BMP
+    /// * `description_path`: The path to the description file, whose format is documented on `parse`
+    ///
+    /// returns: Result<Skin, String> An error if either file can't be read or is malformed
+    pub fn load<P: AsRef<Path>, Q: AsRef<Path>>(
+        atlas_path: P,
+        description_path: Q,
+    ) -> Result<Skin, String> {
+        let atlas = ImageData::load_bmp(atlas_path)?;
+        let description = fs::read_to_string(description_path).map_err(|e| e.to_string())?;
+        Skin::parse(atlas, &description)
+    }
+
+    /// Creates a skin from an atlas and the contents of a description file
+    ///
+    /// Each non-empty line not starting with `#` describes one part:
+    ///
+    /// ```text
+    /// # style   state   x  y  w  h   left top right bottom
+    /// button    normal  0  0  16 16  4    4   4     4
+    /// button    hover   16 0  16 16  4    4   4     4
+    /// ```
+    ///
+    /// The state is one of `normal`, `hover`, `pressed`, `disabled` or `focused`.
+    ///
+    /// # Arguments
+    ///
+    /// * `atlas`: The atlas image
+    /// * `description`: The description file's contents
+    ///
+    /// returns: Result<Skin, String> An error naming the first malformed line
+    pub fn parse(atlas: ImageData, description: &str) -> Result<Skin, String> {
+        let mut parts = HashMap::new();
+
+        for (i, line) in description.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |reason: &str| format!("Line {}: {}", i + 1, reason);
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 10 {
+                return Err(error(&format!("expected 10 fields, got {}", fields.len())));
+            }
+            let state = parse_state(fields[1])
+                .ok_or_else(|| error(&format!("unknown state `{}`", fields[1])))?;
+            let numbers = fields[2..]
+                .iter()
+                .map(|x| x.parse::<f32>())
+                .collect::<Result<Vec<f32>, _>>()
+                .map_err(|e| error(&e.to_string()))?;
+            if numbers.iter().any(|x| !x.is_finite() || *x < 0.0) {
+                return Err(error(
+                    "positions, sizes and margins must be finite and non-negative",
+                ));
+            }
+
+            let part = SkinPart {
+                source: Rect::new(numbers[0], numbers[1], numbers[2], numbers[3]),
+                margins: Thickness::new(numbers[4], numbers[5], numbers[6], numbers[7]),
+            };
+            if part.source.right() > atlas.width as f32
+                || part.source.bottom() > atlas.height as f32
+            {
+                return Err(error("region exceeds the atlas"));
+            }
+            if part.margins.horizontal() > part.source.w || part.margins.vertical() > part.source.h
+            {
+                return Err(error("margins exceed the region"));
+            }
+            parts.insert((fields[0].to_string(), state), part);
+        }
+
+        Ok(Skin { atlas, parts })
+    }
+
+    /// Gets the part for a style in the specified visual state, falling back to the normal state
+    pub fn part(&self, style: &str, visual_state: VisualState) -> Option<&SkinPart> {
+        self.parts
+            .get(&(style.to_string(), visual_state))
+            .or_else(|| self.parts.get(&(style.to_string(), VisualState::Normal)))
+    }
+}
+
+/// Splits a span into its start margin, stretched middle and end margin as (source offset, source size, destination offset, destination size)
+fn slices(source: f32, destination: f32, start: f32, end: f32) -> [(f32, f32, f32, f32); 3] {
+    // Margins are shrunk proportionally when the destination is too small to fit them
+    let scale = if start + end > destination && start + end > 0.0 {
+        destination / (start + end)
+    } else {
+        1.0
+    };
+    let start_destination = start * scale;
+    let end_destination = end * scale;
+
+    [
+        (0.0, start, 0.0, start_destination),
+        (
+            start,
+            source - start - end,
+            start_destination,
+            destination - start_destination - end_destination,
+        ),
+        (
+            source - end,
+            end,
+            destination - end_destination,
+            end_destination,
+        ),
+    ]
+}
+
+/// Draws a skin part stretched to the destination via nine-slice scaling
+pub(crate) fn draw_part(
+    skin: &Skin,
+    part: &SkinPart,
+    destination: Rect,
+    renderer: &mut dyn Renderer,
+) {
+    let columns = slices(
+        part.source.w,
+        destination.w,
+        part.margins.left,
+        part.margins.right,
+    );
+    let rows = slices(
+        part.source.h,
+        destination.h,
+        part.margins.top,
+        part.margins.bottom,
+    );
+
+    for (source_y, source_h, destination_y, destination_h) in rows {
+        for (source_x, source_w, destination_x, destination_w) in columns {
+            // Edges are rounded rather than truncated, so neighbouring slices don't leave gaps
+            let left = (destination.x + destination_x).round();
+            let top = (destination.y + destination_y).round();
+            let right = (destination.x + destination_x + destination_w).round();
+            let bottom = (destination.y + destination_y + destination_h).round();
+            if source_w <= 0.0 || source_h <= 0.0 || right <= left || bottom <= top {
+                continue;
+            }
+            renderer.draw_image(
+                &skin.atlas,
+                Rect::new(
+                    part.source.x + source_x,
+                    part.source.y + source_y,
+                    source_w,
+                    source_h,
+                ),
+                Rect::new(left, top, right - left, bottom - top),
+            );
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use crate::controls::control::Control;
+use crate::core::geo::{FlowDirection, Point, Rect};
+use crate::core::messages::Message;
+use crate::render::TextMeasurer;
+use crate::testing::layout::FixedAdvanceMeasurer;
+use crate::theme::Theme;
+use sdl2::keyboard::{Keycode, Mod};
+use std::time::Duration;
+
+/// The time each frame advances by default, matching a 60 Hz display
+pub const DEFAULT_FRAME_TIME: Duration = Duration::from_nanos(16_666_667);
+
+/// Drives a control tree from synthetic input, the way a window drives it from SDL events
+///
+/// Messages go through the same routing as a window's, and the tree is laid out again after each one which affects
+/// layout, so bounds are always current. Time only advances when frames are stepped, by a fixed amount per frame.
+pub struct TestDriver {
+    content: Control,
+    theme: Theme,
+    measurer: Box<dyn TextMeasurer>,
+    viewport: Rect,
+    frame_time: Duration,
+    modifiers: Mod,
+}
+
+/// Gets the text a control displays, if it displays any
+fn text_of(control: &Control) -> Option<String> {
+    match control {
+        Control::Label { text, .. } => Some(text.clone()),
+        Control::RichText { spans, .. } => Some(spans.iter().map(|x| x.text.as_str()).collect()),
+        _ => None,
+    }
+}
+
+impl TestDriver {
+    /// Creates a driver for a tree filling a viewport of the specified size
+    ///
+    /// Text is measured by a `FixedAdvanceMeasurer` unless another measurer is set.
+    pub fn new(content: Control, width: f32, height: f32) -> TestDriver {
+        let mut driver = TestDriver {
+            content,
+            theme: Theme::default(),
+            measurer: Box::new(FixedAdvanceMeasurer::default()),
+            viewport: Rect::new(0.0, 0.0, width, height),
+            frame_time: DEFAULT_FRAME_TIME,
+            modifiers: Mod::NOMOD,
+        };
+        driver.layout();
+        driver
+    }
+    pub fn theme(mut self, theme: Theme) -> TestDriver {
+        self.theme = theme;
+        self.content.invalidate_layout();
+        self.layout();
+        self
+    }
+    pub fn measurer<M: TextMeasurer + 'static>(mut self, measurer: M) -> TestDriver {
+        self.measurer = Box::new(measurer);
+        self.content.invalidate_layout();
+        self.layout();
+        self
+    }
+    /// Sets the time each stepped frame advances animations by
+    pub fn frame_time(mut self, frame_time: Duration) -> TestDriver {
+        self.frame_time = frame_time;
+        self
+    }
+
+    pub fn content(&self) -> &Control {
+        &self.content
+    }
+
+    /// Gets mutable access to the tree
+    ///
+    /// Changes made through the tree's mutation methods, such as `Control::modify`, are laid out by the next message
+    /// or frame.
+    pub fn content_mut(&mut self) -> &mut Control {
+        &mut self.content
+    }
+
+    /// Lays out the tree if anything affecting its layout changed
+    fn layout(&mut self) {
+        if !self.content.needs_layout() {
+            return;
+        }
+        self.content.do_layout(
+            self.viewport,
+            FlowDirection::default(),
+            self.measurer.as_mut(),
+            &self.theme,
+        );
+    }
+
+    /// Changes the viewport's size, as resizing a window would
+    pub fn resize(&mut self, width: f32, height: f32) {
+        self.viewport = Rect::new(0.0, 0.0, width, height);
+        self.content.invalidate_layout();
+        self.layout();
+    }
+
+    /// Finds the control with the specified id
+    ///
+    /// # Panics
+    ///
+    /// If more than one control has the id
+    pub fn find_by_id(&self, id: &str) -> Option<&Control> {
+        self.content.find_by_id(id).unwrap()
+    }
+
+    /// Finds the first control displaying exactly the specified text
+    pub fn find_by_text(&self, text: &str) -> Option<&Control> {
+        self.content
+            .find(&|x| text_of(x).is_some_and(|x| x == text))
+    }
+
+    /// Gets the text displayed by the control with the specified id
+    pub fn text_of(&self, id: &str) -> Option<String> {
+        self.find_by_id(id).and_then(text_of)
+    }
+
+    /// Gets the center of the control with the specified id, which is where it's clicked
+    pub fn center_of(&self, id: &str) -> Result<Point, String> {
+        let control = self
+            .find_by_id(id)
+            .ok_or_else(|| format!("No control with id `{}`", id))?;
+        let bounds = control.get_base().computed_bounds;
+        Ok(Point {
+            x: bounds.x + bounds.w / 2.0,
+            y: bounds.y + bounds.h / 2.0,
+        })
+    }
+
+    /// Sets the modifier keys reported with key presses and clicks
+    pub fn set_modifiers(&mut self, modifiers: Mod) {
+        self.modifiers = modifiers;
+    }
+
+    /// Routes a message through the tree, then lays it out again if needed
+    ///
+    /// returns: bool Whether the message changed how the tree looks, which is when a window draws it again
+    pub fn send(&mut self, message: Message) -> bool {
+        let changed = self.content.process_message(message);
+        self.layout();
+        changed
+    }
+
+    pub fn mouse_move(&mut self, position: Point) {
+        self.send(Message::MouseMove(position));
+    }
+    pub fn mouse_down(&mut self, position: Point) {
+        self.send(Message::MouseDown(position, self.modifiers));
+    }
+    pub fn mouse_up(&mut self, position: Point) {
+        self.send(Message::MouseUp(position));
+    }
+
+    /// Scrolls the mouse wheel
+    pub fn scroll(&mut self, delta: Point) {
+        self.send(Message::MouseWheel(delta));
+    }
+
+    /// Moves the mouse to a position and clicks the primary button there
+    pub fn click(&mut self, position: Point) {
+        self.mouse_move(position);
+        self.mouse_down(position);
+        self.mouse_up(position);
+    }
+
+    /// Clicks the center of the control with the specified id
+    pub fn click_id(&mut self, id: &str) -> Result<(), String> {
+        let position = self.center_of(id)?;
+        self.click(position);
+        Ok(())
+    }
+
+    /// Clicks the center of the first control displaying exactly the specified text
+    pub fn click_text(&mut self, text: &str) -> Result<(), String> {
+        let bounds = self
+            .find_by_text(text)
+            .ok_or_else(|| format!("No control displays `{}`", text))?
+            .get_base()
+            .computed_bounds;
+        self.click(Point {
+            x: bounds.x + bounds.w / 2.0,
+            y: bounds.y + bounds.h / 2.0,
+        });
+        Ok(())
+    }
+
+    /// Presses a key along with the current modifiers
+    pub fn key_down(&mut self, keycode: Keycode) {
+        self.send(Message::KeyDown(keycode, self.modifiers));
+    }
+
+    /// Types a string, as one text input message
+    pub fn type_text(&mut self, text: &str) {
+        self.send(Message::TextInput(text.to_string()));
+    }
+
+    /// Advances animations by one frame, then lays the tree out again if needed
+    pub fn step(&mut self) {
+        self.content.update(self.frame_time);
+        self.layout();
+    }
+
+    /// Advances by the specified number of frames
+    pub fn step_frames(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.step();
+        }
+    }
+}
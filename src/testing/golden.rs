@@ -0,0 +1,198 @@
+use crate::controls::control::Control;
+use crate::fonts::FontRegistry;
+use crate::render::framebuffer::Framebuffer;
+use crate::render::headless::HeadlessRenderer;
+use crate::theme::Theme;
+use sdl2::pixels::Color;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The environment variable which, when set to anything but `0`, makes missing and mismatching references get replaced by the actual images
+pub const BLESS_VARIABLE: &str = "UGUI_BLESS";
+
+/// The result of comparing two images pixel by pixel
+#[derive(Clone, PartialEq, Debug)]
+pub struct Comparison {
+    /// The number of pixels with a channel differing by more than the tolerance
+    pub mismatched: usize,
+
+    /// The largest difference found in any channel
+    pub max_difference: u8,
+
+    /// The actual image dimmed to grey, with mismatched pixels highlighted in red
+    pub diff: Framebuffer,
+}
+
+/// Compares two images of the same size
+///
+/// # Arguments
+///
+/// * `actual`: The rendered image
+/// * `expected`: The reference image
+/// * `tolerance`: The largest difference allowed in any channel of a pixel
+///
+/// returns: Option<Comparison> `None` if the images differ in size
+pub fn compare(actual: &Framebuffer, expected: &Framebuffer, tolerance: u8) -> Option<Comparison> {
+    if actual.width() != expected.width() || actual.height() != expected.height() {
+        return None;
+    }
+
+    let mut comparison = Comparison {
+        mismatched: 0,
+        max_difference: 0,
+        diff: Framebuffer::new(actual.width(), actual.height()),
+    };
+    for y in 0..actual.height() {
+        for x in 0..actual.width() {
+            let a = actual.pixel(x, y);
+            let e = expected.pixel(x, y);
+            let difference = [(a.r, e.r), (a.g, e.g), (a.b, e.b), (a.a, e.a)]
+                .iter()
+                .map(|(a, e)| a.abs_diff(*e))
+                .max()
+                .unwrap();
+            comparison.max_difference = comparison.max_difference.max(difference);
+
+            let color = if difference > tolerance {
+                comparison.mismatched += 1;
+                Color::RGB(255, 0, 0)
+            } else {
+                let grey = ((a.r as u32 + a.g as u32 + a.b as u32) / 3 / 4 + 160) as u8;
+                Color::RGB(grey, grey, grey)
+            };
+            comparison.diff.blend_pixel(x as i32, y as i32, color);
+        }
+    }
+    Some(comparison)
+}
+
+/// Renders control trees offscreen and compares them to reference images
+///
+/// References are stored as BMP files. When a reference doesn't match, the actual image and a diff are written next to it,
+/// as `<name>.actual.bmp` and `<name>.diff.bmp`. Missing references are errors too, so a reference which was never committed
+/// can't pass silently; they're only recorded when `UGUI_BLESS` is set.
+pub struct GoldenTest {
+    width: u32,
+    height: u32,
+    tolerance: u8,
+    theme: Theme,
+    fonts: FontRegistry,
+}
+
+impl GoldenTest {
+    /// Creates a test rendering at the specified size with the default theme and fonts, and no tolerance
+    pub fn new(width: u32, height: u32) -> GoldenTest {
+        GoldenTest {
+            width,
+            height,
+            tolerance: 0,
+            theme: Theme::default(),
+            fonts: FontRegistry::new(),
+        }
+    }
+    /// Sets the largest difference allowed in any channel of a pixel, to absorb font rasterization differences between machines
+    pub fn tolerance(mut self, tolerance: u8) -> GoldenTest {
+        self.tolerance = tolerance;
+        self
+    }
+    pub fn theme(mut self, theme: Theme) -> GoldenTest {
+        self.theme = theme;
+        self
+    }
+    pub fn fonts(mut self, fonts: FontRegistry) -> GoldenTest {
+        self.fonts = fonts;
+        self
+    }
+
+    /// Renders a control tree into a framebuffer of the test's size
+    pub fn render(&self, content: &mut Control) -> Result<Framebuffer, String> {
+        let mut renderer = HeadlessRenderer::new(self.width, self.height, self.fonts.clone())?;
+        renderer.render_tree(content, &self.theme);
+        Ok(renderer.into_framebuffer())
+    }
+
+    /// Renders a control tree and compares it to a reference image
+    ///
+    /// # Arguments
+    ///
+    /// * `content`: The tree to render
+    /// * `reference`: The path to the reference BMP
+    ///
+    /// returns: Result<(), String> An error describing the mismatch, or why the images couldn't be compared
+    pub fn check<P: AsRef<Path>>(&self, content: &mut Control, reference: P) -> Result<(), String> {
+        let reference = reference.as_ref();
+        let actual = self.render(content)?;
+        let actual_path = sibling(reference, "actual");
+        let diff_path = sibling(reference, "diff");
+
+        if blessing() {
+            if let Some(parent) = reference.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            remove_outputs(&actual_path, &diff_path);
+            return actual.save_bmp(reference);
+        }
+        if !reference.exists() {
+            if let Some(parent) = reference.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            actual.save_bmp(&actual_path)?;
+            return Err(format!(
+                "{} doesn't exist; wrote {}. Set {}=1 to record it",
+                reference.display(),
+                actual_path.display(),
+                BLESS_VARIABLE
+            ));
+        }
+
+        let expected = Framebuffer::load_bmp(reference)?;
+        let Some(comparison) = compare(&actual, &expected, self.tolerance) else {
+            actual.save_bmp(&actual_path)?;
+            return Err(format!(
+                "{} is {}x{}, but the actual image is {}x{}; wrote {}",
+                reference.display(),
+                expected.width(),
+                expected.height(),
+                actual.width(),
+                actual.height(),
+                actual_path.display()
+            ));
+        };
+        if comparison.mismatched > 0 {
+            actual.save_bmp(&actual_path)?;
+            comparison.diff.save_bmp(&diff_path)?;
+            return Err(format!(
+                "{} pixels differ from {} by up to {} (tolerance {}); wrote {} and {}. Set {}=1 to update the reference",
+                comparison.mismatched,
+                reference.display(),
+                comparison.max_difference,
+                self.tolerance,
+                actual_path.display(),
+                diff_path.display(),
+                BLESS_VARIABLE
+            ));
+        }
+        remove_outputs(&actual_path, &diff_path);
+        Ok(())
+    }
+}
+
+fn blessing() -> bool {
+    env::var(BLESS_VARIABLE).is_ok_and(|x| !x.is_empty() && x != "0")
+}
+
+/// Gets the path of an output written next to a reference, such as `button.actual.bmp` for `button.bmp`
+fn sibling(reference: &Path, suffix: &str) -> PathBuf {
+    let stem = reference
+        .file_stem()
+        .map(|x| x.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    reference.with_file_name(format!("{}.{}.bmp", stem, suffix))
+}
+
+/// Removes the outputs of a previous failed comparison, so they don't outlive the failure
+fn remove_outputs(actual_path: &Path, diff_path: &Path) {
+    let _ = fs::remove_file(actual_path);
+    let _ = fs::remove_file(diff_path);
+}
@@ -0,0 +1,118 @@
+use crate::controls::control::Control;
+use crate::core::geo::{FlowDirection, Point, Rect};
+use crate::fonts::FontKey;
+use crate::render::TextMeasurer;
+use crate::theme::Theme;
+use std::fmt::Write;
+
+/// Measures text as if every character had the same advance, so layouts don't depend on fonts
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FixedAdvanceMeasurer {
+    /// The width of every character, in pixels
+    pub advance: f32,
+
+    /// The height of a line, in pixels
+    pub line_height: f32,
+}
+
+impl Default for FixedAdvanceMeasurer {
+    fn default() -> Self {
+        FixedAdvanceMeasurer {
+            advance: 8.0,
+            line_height: 16.0,
+        }
+    }
+}
+
+impl TextMeasurer for FixedAdvanceMeasurer {
+    fn has_glyph(&mut self, _font: &FontKey, _c: char) -> bool {
+        true
+    }
+
+    fn font_for(&mut self, font: &FontKey, _c: char) -> FontKey {
+        font.clone()
+    }
+
+    fn glyph_advance(&mut self, _font: &FontKey, _c: char) -> f32 {
+        self.advance
+    }
+
+    fn measure(&mut self, _font: &FontKey, text: &str) -> Point {
+        Point {
+            x: text.chars().count() as f32 * self.advance,
+            y: self.line_height,
+        }
+    }
+
+    fn height(&mut self, _font: &FontKey) -> f32 {
+        self.line_height
+    }
+
+    fn ascent(&mut self, _font: &FontKey) -> f32 {
+        self.line_height * 0.75
+    }
+
+    fn line_spacing(&mut self, _font: &FontKey) -> f32 {
+        self.line_height
+    }
+}
+
+/// Dumps the bounds computed by the last layout of a tree, one control per line
+///
+/// Children are indented by two spaces below their parent, and labels are followed by their text:
+///
+/// ```text
+/// stack (100, 200, 240, 64)
+///   label "Start" (100, 200, 40, 16)
+/// ```
+pub fn dump_bounds(content: &Control) -> String {
+    fn dump(control: &Control, depth: usize, output: &mut String) {
+        let base = control.get_base();
+        let bounds = base.computed_bounds;
+        output.push_str(&"  ".repeat(depth));
+        output.push_str(control.kind());
+        if let Control::Label { text, .. } = control {
+            write!(output, " {:?}", text).unwrap();
+        }
+        write!(
+            output,
+            " ({}, {}, {}, {})",
+            bounds.x, bounds.y, bounds.w, bounds.h
+        )
+        .unwrap();
+        if !base.visible {
+            output.push_str(" hidden");
+        }
+        output.push('\n');
+        for child in &base.children {
+            dump(child, depth + 1, output);
+        }
+    }
+
+    let mut output = String::new();
+    dump(content, 0, &mut output);
+    output
+}
+
+/// Lays out a tree inside a viewport and dumps the resulting bounds, for comparison with a stored snapshot
+///
+/// The whole tree is laid out, even the parts which are clean from an earlier layout.
+///
+/// # Arguments
+///
+/// * `content`: The tree to lay out
+/// * `viewport`: The region the tree fills, as a window's client area would
+/// * `measurer`: Measures text. `FixedAdvanceMeasurer` keeps snapshots independent of fonts
+/// * `theme`: The theme the tree is laid out with
+///
+/// returns: String The bounds in the format of `dump_bounds`
+pub fn layout_snapshot(
+    content: &mut Control,
+    viewport: Rect,
+    measurer: &mut dyn TextMeasurer,
+    theme: &Theme,
+) -> String {
+    content.invalidate_layout();
+    content.do_layout(viewport, FlowDirection::default(), measurer, theme);
+    dump_bounds(content)
+}
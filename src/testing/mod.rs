@@ -0,0 +1,3 @@
+pub mod driver;
+pub mod golden;
+pub mod layout;
@@ -0,0 +1,322 @@
+use crate::core::geo::FlowDirection;
+
+/// The direction in which text flows
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum TextDirection {
+    #[default]
+    LeftToRight,
+    RightToLeft,
+}
+
+impl From<FlowDirection> for TextDirection {
+    fn from(value: FlowDirection) -> Self {
+        match value {
+            FlowDirection::LeftToRight => TextDirection::LeftToRight,
+            FlowDirection::RightToLeft => TextDirection::RightToLeft,
+        }
+    }
+}
+
+/// The bidirectional character types relevant to implicit resolution
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum BidiClass {
+    /// Strong left-to-right
+    L,
+    /// Strong right-to-left
+    R,
+    /// Arabic letter
+    AL,
+    /// European number
+    EN,
+    /// Arabic number
+    AN,
+    /// Number separator or terminator
+    Separator,
+    /// Nonspacing mark, which takes the type of its base
+    Mark,
+    /// Whitespace
+    WS,
+    /// Other neutral
+    ON,
+}
+
+fn in_ranges(c: u32, ranges: &[(u32, u32)]) -> bool {
+    ranges.iter().any(|&(start, end)| c >= start && c <= end)
+}
+
+/// Whether a character is a combining mark, which stays attached to the preceding character
+pub(crate) fn is_mark(c: char) -> bool {
+    in_ranges(
+        c as u32,
+        &[
+            (0x0300, 0x036F),
+            (0x0483, 0x0489),
+            (0x0591, 0x05BD),
+            (0x05BF, 0x05BF),
+            (0x05C1, 0x05C2),
+            (0x05C4, 0x05C5),
+            (0x05C7, 0x05C7),
+            (0x0610, 0x061A),
+            (0x064B, 0x065F),
+            (0x0670, 0x0670),
+            (0x06D6, 0x06DC),
+            (0x06DF, 0x06E4),
+            (0x06E7, 0x06E8),
+            (0x06EA, 0x06ED),
+            (0x0900, 0x0903),
+            (0x093A, 0x094F),
+            (0x0951, 0x0957),
+            (0x0962, 0x0963),
+            (0x0E31, 0x0E31),
+            (0x0E34, 0x0E3A),
+            (0x0E47, 0x0E4E),
+            (0x200C, 0x200D),
+            (0x20D0, 0x20FF),
+            (0xFE00, 0xFE0F),
+            (0xFE20, 0xFE2F),
+        ],
+    )
+}
+
+fn classify(c: char) -> BidiClass {
+    let code = c as u32;
+    if is_mark(c) {
+        return BidiClass::Mark;
+    }
+    if c.is_ascii_digit() || (0x06F0..=0x06F9).contains(&code) {
+        return BidiClass::EN;
+    }
+    if in_ranges(code, &[(0x0660, 0x0669), (0x066B, 0x066C)]) {
+        return BidiClass::AN;
+    }
+    if in_ranges(
+        code,
+        &[
+            (0x0590, 0x05FF),
+            (0x07C0, 0x085F),
+            (0xFB1D, 0xFB4F),
+            (0x10800, 0x10FFF),
+        ],
+    ) {
+        return BidiClass::R;
+    }
+    if in_ranges(
+        code,
+        &[
+            (0x0600, 0x07BF),
+            (0x0860, 0x08FF),
+            (0xFB50, 0xFDFF),
+            (0xFE70, 0xFEFF),
+        ],
+    ) {
+        return BidiClass::AL;
+    }
+    if matches!(c, '+' | '-' | '#' | '$' | '%' | ',' | '.' | ':' | '/' | '°') {
+        return BidiClass::Separator;
+    }
+    if c.is_whitespace() {
+        return BidiClass::WS;
+    }
+    if c.is_alphanumeric() {
+        return BidiClass::L;
+    }
+    BidiClass::ON
+}
+
+fn is_strong_rtl(class: BidiClass) -> bool {
+    matches!(class, BidiClass::R | BidiClass::AL)
+}
+
+/// Detects the direction of a paragraph from its first strong character, defaulting to left-to-right
+pub fn detect_direction(text: &str) -> TextDirection {
+    text.chars()
+        .map(classify)
+        .find(|x| matches!(x, BidiClass::L | BidiClass::R | BidiClass::AL))
+        .map(|x| {
+            if is_strong_rtl(x) {
+                TextDirection::RightToLeft
+            } else {
+                TextDirection::LeftToRight
+            }
+        })
+        .unwrap_or_default()
+}
+
+/// Resolves the embedding level of each character of a paragraph, following the implicit rules of the Unicode Bidirectional Algorithm
+///
+/// Even levels are left-to-right, odd levels are right-to-left.
+/// Explicit embeddings, overrides and isolates aren't supported.
+///
+/// # Arguments
+///
+/// * `text`: The paragraph's text
+/// * `direction`: The paragraph's base direction
+///
+/// returns: Vec<u8> One level per character
+pub fn resolve_levels(text: &str, direction: TextDirection) -> Vec<u8> {
+    let paragraph_level: u8 = match direction {
+        TextDirection::LeftToRight => 0,
+        TextDirection::RightToLeft => 1,
+    };
+    let sor = if paragraph_level == 0 {
+        BidiClass::L
+    } else {
+        BidiClass::R
+    };
+    let mut classes: Vec<BidiClass> = text.chars().map(classify).collect();
+    let original = classes.clone();
+
+    // W1: marks take the type of the preceding character
+    for i in 0..classes.len() {
+        if classes[i] == BidiClass::Mark {
+            classes[i] = if i == 0 { sor } else { classes[i - 1] };
+        }
+    }
+
+    // W2, W3: numbers after Arabic letters are Arabic numbers, and Arabic letters are right-to-left
+    let mut last_strong = sor;
+    for class in classes.iter_mut() {
+        match *class {
+            BidiClass::L | BidiClass::R | BidiClass::AL => last_strong = *class,
+            BidiClass::EN if last_strong == BidiClass::AL => *class = BidiClass::AN,
+            _ => {}
+        }
+    }
+    for class in classes.iter_mut() {
+        if *class == BidiClass::AL {
+            *class = BidiClass::R;
+        }
+    }
+
+    // W4: a single separator between two numbers of the same type joins them
+    for i in 1..classes.len().saturating_sub(1) {
+        if classes[i] == BidiClass::Separator
+            && matches!(classes[i - 1], BidiClass::EN | BidiClass::AN)
+            && classes[i - 1] == classes[i + 1]
+        {
+            classes[i] = classes[i - 1];
+        }
+    }
+
+    // W5: terminators adjacent to European numbers are part of them
+    for i in 0..classes.len() {
+        if classes[i] != BidiClass::EN {
+            continue;
+        }
+        let mut j = i;
+        while j > 0 && classes[j - 1] == BidiClass::Separator {
+            j -= 1;
+            classes[j] = BidiClass::EN;
+        }
+        let mut j = i + 1;
+        while j < classes.len() && classes[j] == BidiClass::Separator {
+            classes[j] = BidiClass::EN;
+            j += 1;
+        }
+    }
+
+    // W6: remaining separators are neutral
+    for class in classes.iter_mut() {
+        if *class == BidiClass::Separator {
+            *class = BidiClass::ON;
+        }
+    }
+
+    // W7: European numbers in left-to-right context are left-to-right
+    let mut last_strong = sor;
+    for class in classes.iter_mut() {
+        match *class {
+            BidiClass::L | BidiClass::R => last_strong = *class,
+            BidiClass::EN if last_strong == BidiClass::L => *class = BidiClass::L,
+            _ => {}
+        }
+    }
+
+    // N1, N2: neutrals between characters of the same direction take that direction, otherwise the paragraph's
+    let strong_direction = |class: BidiClass| match class {
+        BidiClass::L => Some(BidiClass::L),
+        BidiClass::R | BidiClass::EN | BidiClass::AN => Some(BidiClass::R),
+        _ => None,
+    };
+    let mut i = 0;
+    while i < classes.len() {
+        if strong_direction(classes[i]).is_some() {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < classes.len() && strong_direction(classes[i]).is_none() {
+            i += 1;
+        }
+        let before = if start == 0 {
+            sor
+        } else {
+            strong_direction(classes[start - 1]).unwrap()
+        };
+        let after = classes
+            .get(i)
+            .and_then(|x| strong_direction(*x))
+            .unwrap_or(sor);
+        let resolved = if before == after { before } else { sor };
+        for class in &mut classes[start..i] {
+            *class = resolved;
+        }
+    }
+
+    // I1, I2: implicit levels
+    let mut levels: Vec<u8> = classes
+        .iter()
+        .map(|class| match (paragraph_level, class) {
+            (0, BidiClass::R) => 1,
+            (0, BidiClass::AN | BidiClass::EN) => 2,
+            (0, _) => 0,
+            (_, BidiClass::L | BidiClass::EN | BidiClass::AN) => paragraph_level + 1,
+            _ => paragraph_level,
+        })
+        .collect();
+
+    // L1: trailing whitespace is reset to the paragraph level
+    for (level, class) in levels.iter_mut().zip(&original).rev() {
+        if *class != BidiClass::WS {
+            break;
+        }
+        *level = paragraph_level;
+    }
+
+    levels
+}
+
+/// Computes the visual order of items with the specified levels
+///
+/// returns: Vec<usize> The logical indices of the items, from left to right
+pub fn reorder(levels: &[u8]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..levels.len()).collect();
+    let Some(&highest) = levels.iter().max() else {
+        return order;
+    };
+    let lowest_odd = levels
+        .iter()
+        .copied()
+        .filter(|x| x % 2 == 1)
+        .min()
+        .unwrap_or(highest + 1);
+
+    // L2: from the highest level down to the lowest odd level, reverse every run at that level or higher
+    let mut level = highest;
+    while level >= lowest_odd && level > 0 {
+        let mut i = 0;
+        while i < order.len() {
+            if levels[order[i]] < level {
+                i += 1;
+                continue;
+            }
+            let start = i;
+            while i < order.len() && levels[order[i]] >= level {
+                i += 1;
+            }
+            order[start..i].reverse();
+        }
+        level -= 1;
+    }
+    order
+}
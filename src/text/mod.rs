@@ -0,0 +1,466 @@
+use crate::core::geo::{FlowDirection, Point};
+use crate::fonts::{FontCache, FontKey};
+use crate::render::{Renderer, TextMeasurer};
+use sdl2::pixels::Color;
+use sdl2::render::{BlendMode, Texture, TextureCreator};
+use sdl2::video::WindowContext;
+use std::collections::HashMap;
+
+pub mod bidi;
+pub mod script;
+pub mod shaping;
+
+use bidi::TextDirection;
+
+/// The number of strings a `TextCache` keeps textures for by default
+pub const DEFAULT_TEXT_CACHE_CAPACITY: usize = 512;
+
+/// Identifies a rendered string
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+struct TextKey {
+    text: String,
+    font: FontKey,
+    color: Color,
+}
+
+struct TextEntry {
+    texture: Texture,
+    size: Point,
+
+    /// The value of the cache's clock when the entry was last drawn
+    last_used: u64,
+}
+
+/// Counters describing how effective a `TextCache` is
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct TextCacheStats {
+    /// The number of lookups which found a cached texture
+    pub hits: u64,
+
+    /// The number of lookups which had to render the string
+    pub misses: u64,
+
+    /// The number of textures destroyed to make room for new ones
+    pub evictions: u64,
+
+    /// The number of textures currently cached
+    pub entries: usize,
+}
+
+/// Caches rendered strings as textures, keyed by their text, font and color
+///
+/// When the cache is full, the least recently drawn string is evicted.
+pub struct TextCache {
+    texture_creator: TextureCreator<WindowContext>,
+    entries: HashMap<TextKey, TextEntry>,
+    capacity: usize,
+    clock: u64,
+    stats: TextCacheStats,
+}
+
+impl TextCache {
+    pub fn new(texture_creator: TextureCreator<WindowContext>, capacity: usize) -> TextCache {
+        TextCache {
+            texture_creator,
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+            stats: TextCacheStats::default(),
+        }
+    }
+
+    /// The maximum number of strings kept at once
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Changes the maximum number of strings kept at once, evicting the least recently drawn ones if needed
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.entries.len() > self.capacity {
+            self.evict();
+        }
+    }
+
+    /// Gets the cache's counters
+    pub fn stats(&self) -> TextCacheStats {
+        TextCacheStats {
+            entries: self.entries.len(),
+            ..self.stats
+        }
+    }
+
+    /// Resets the hit, miss and eviction counters
+    pub fn reset_stats(&mut self) {
+        self.stats = TextCacheStats::default();
+    }
+
+    /// Gets the texture of a string along with its size, rendering it if it isn't cached yet
+    ///
+    /// # Arguments
+    ///
+    /// * `fonts`: The cache to load the font from
+    /// * `font`: The key of the font to render with
+    /// * `color`: The text color
+    /// * `text`: The string to render. Empty strings have no texture
+    ///
+    /// returns: Result<Option<(&Texture, Point)>, String> An error if the string can't be rendered
+    pub fn get(
+        &mut self,
+        fonts: &mut FontCache,
+        font: &FontKey,
+        color: Color,
+        text: &str,
+    ) -> Result<Option<(&Texture, Point)>, String> {
+        if text.is_empty() {
+            return Ok(None);
+        }
+        self.clock += 1;
+        let key = TextKey {
+            text: text.to_string(),
+            font: font.clone(),
+            color,
+        };
+
+        if let Some(entry) = self.entries.get_mut(&key) {
+            self.stats.hits += 1;
+            entry.last_used = self.clock;
+        } else {
+            self.stats.misses += 1;
+            let surface = fonts
+                .get(font)?
+                .render(text)
+                .blended(color)
+                .map_err(|e| e.to_string())?;
+            let mut texture = self
+                .texture_creator
+                .create_texture_from_surface(&surface)
+                .map_err(|e| e.to_string())?;
+            texture.set_blend_mode(BlendMode::Blend);
+
+            if self.entries.len() >= self.capacity {
+                self.evict();
+            }
+            self.entries.insert(
+                key.clone(),
+                TextEntry {
+                    texture,
+                    size: Point {
+                        x: surface.width() as f32,
+                        y: surface.height() as f32,
+                    },
+                    last_used: self.clock,
+                },
+            );
+        }
+
+        let entry = &self.entries[&key];
+        Ok(Some((&entry.texture, entry.size)))
+    }
+
+    /// Destroys the least recently drawn string's texture
+    fn evict(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(entry) = oldest.and_then(|key| self.entries.remove(&key)) {
+            self.stats.evictions += 1;
+            // SAFETY: the texture was created by this cache's texture creator, whose renderer is still alive
+            unsafe { entry.texture.destroy() };
+        }
+    }
+}
+
+impl Drop for TextCache {
+    fn drop(&mut self) {
+        for (_, entry) in self.entries.drain() {
+            // SAFETY: the cache is dropped before the canvas which owns the renderer
+            unsafe { entry.texture.destroy() };
+        }
+    }
+}
+
+/// Measures the size of a line of text once shaped, with missing characters taken from fallback fonts
+pub fn measure(text: &str, font: &FontKey, measurer: &mut dyn TextMeasurer) -> Point {
+    Point {
+        x: shaping::shape_line(text, font, None, measurer).width,
+        y: measurer.height(font),
+    }
+}
+
+/// Draws a line of text with its top-left corner at the specified position
+///
+/// The text is shaped and ordered visually first, so each run is drawn with its own font.
+///
+/// returns: Point The size of the drawn text
+pub(crate) fn draw_text(
+    text: &str,
+    position: Point,
+    font: &FontKey,
+    color: Color,
+    direction: TextDirection,
+    renderer: &mut dyn Renderer,
+) -> Point {
+    let line = shaping::shape_line(text, font, Some(direction), renderer);
+    let ascent = renderer.ascent(font);
+    for run in &line.runs {
+        // Runs drawn with fallback fonts share the preferred font's baseline
+        let y = position.y + ascent - renderer.ascent(&run.font);
+
+        // Snapped to whole pixels, so glyphs aren't resampled
+        let position = Point {
+            x: (position.x + run.x).round(),
+            y: y.round(),
+        };
+        renderer.draw_text(&run.glyphs, position, &run.font, color);
+    }
+    Point {
+        x: line.width,
+        y: renderer.height(font),
+    }
+}
+
+/// The string appended to trimmed lines
+const ELLIPSIS: &str = "…";
+
+/// Describes how text is broken into lines when it's wider than the available space
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TextWrapping {
+    /// Lines are only broken at explicit newlines
+    #[default]
+    None,
+
+    /// Lines are broken between words. Words which don't fit on a line by themselves are broken between characters
+    Word,
+
+    /// Lines are broken between any two characters
+    Character,
+}
+
+/// Describes what happens to text which doesn't fit inside the available space
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TextTrimming {
+    /// The text overflows
+    #[default]
+    None,
+
+    /// The text is cut off at a character, and an ellipsis is shown in its place
+    Ellipsis,
+}
+
+/// Describes the horizontal position of each line relative to the text's bounds
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TextAlignment {
+    #[default]
+    Start,
+    Center,
+    End,
+}
+
+impl TextAlignment {
+    /// Maps the alignment to the physical one it has in a flow direction
+    pub fn resolve(&self, flow_direction: FlowDirection) -> TextAlignment {
+        match (flow_direction, self) {
+            (FlowDirection::RightToLeft, TextAlignment::Start) => TextAlignment::End,
+            (FlowDirection::RightToLeft, TextAlignment::End) => TextAlignment::Start,
+            _ => *self,
+        }
+    }
+}
+
+/// A single line of laid out text
+#[derive(Clone, PartialEq, Debug)]
+pub struct TextLine {
+    pub text: String,
+    pub width: f32,
+}
+
+/// Text broken into lines which fit inside a region
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TextLayout {
+    pub lines: Vec<TextLine>,
+
+    /// The distance between the tops of consecutive lines
+    pub line_height: f32,
+}
+
+impl TextLayout {
+    /// The size of the box enclosing all lines
+    pub fn size(&self) -> Point {
+        Point {
+            x: self.lines.iter().map(|x| x.width).fold(0.0, f32::max),
+            y: self.lines.len() as f32 * self.line_height,
+        }
+    }
+
+    /// Gets the horizontal offset of a line inside a region of the specified width
+    pub fn line_offset(&self, line: &TextLine, width: f32, alignment: TextAlignment) -> f32 {
+        match alignment {
+            TextAlignment::Start => 0.0,
+            TextAlignment::Center => (width - line.width) / 2.0,
+            TextAlignment::End => width - line.width,
+        }
+    }
+}
+
+/// Finds the longest prefix of a string which fits, assuming no prefix fits once a shorter one doesn't
+///
+/// The prefix length is doubled until a prefix doesn't fit, then bisected, so only strings up to twice as long as the
+/// result are measured, a logarithmic number of times.
+fn fitting_prefix(text: &str, mut fits: impl FnMut(&str) -> bool) -> &str {
+    let prefix = |count: usize| {
+        text.char_indices()
+            .nth(count)
+            .map_or(text, |(i, _)| &text[..i])
+    };
+
+    // Prefixes of `fitting` characters fit, and prefixes of `overflowing` characters don't
+    let mut fitting = 0;
+    let mut overflowing = 1;
+    loop {
+        let candidate = prefix(overflowing);
+        if !fits(candidate) {
+            break;
+        }
+        if candidate.len() == text.len() {
+            return text;
+        }
+        fitting = overflowing;
+        overflowing *= 2;
+    }
+    while overflowing - fitting > 1 {
+        let middle = (fitting + overflowing) / 2;
+        if fits(prefix(middle)) {
+            fitting = middle;
+        } else {
+            overflowing = middle;
+        }
+    }
+    prefix(fitting)
+}
+
+/// Breaks a string into lines no wider than the width, keeping at least its first character on each line
+fn break_characters(
+    text: &str,
+    max_width: f32,
+    measure: &mut impl FnMut(&str) -> f32,
+) -> Vec<String> {
+    let mut lines = vec![];
+    let mut rest = text;
+    loop {
+        let mut line = fitting_prefix(rest, |x| measure(x) <= max_width);
+        if line.is_empty() {
+            line = rest.chars().next().map_or(rest, |c| &rest[..c.len_utf8()]);
+        }
+        lines.push(line.to_string());
+        rest = &rest[line.len()..];
+        if rest.is_empty() {
+            return lines;
+        }
+    }
+}
+
+/// Breaks a paragraph into lines between words, falling back to characters for words which are too long
+fn break_words(
+    paragraph: &str,
+    max_width: f32,
+    measure: &mut impl FnMut(&str) -> f32,
+) -> Vec<String> {
+    let mut lines = vec![];
+    let mut current = String::new();
+    for word in paragraph.split(' ') {
+        let candidate = if current.is_empty() {
+            word.to_string()
+        } else {
+            format!("{} {}", current, word)
+        };
+        if measure(&candidate) <= max_width {
+            current = candidate;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        let mut pieces = break_characters(word, max_width, measure);
+        current = pieces.pop().unwrap_or_default();
+        lines.append(&mut pieces);
+    }
+    lines.push(current);
+    lines
+}
+
+/// Shortens a line until it fits inside the width along with an ellipsis
+fn trim(line: &str, max_width: f32, measure: &mut impl FnMut(&str) -> f32) -> String {
+    let trimmed = fitting_prefix(line.trim_end(), |x| {
+        measure(&format!("{}{}", x.trim_end(), ELLIPSIS)) <= max_width
+    });
+    format!("{}{}", trimmed.trim_end(), ELLIPSIS)
+}
+
+/// Breaks text into lines which fit inside a region
+///
+/// Explicit newlines always start a new line. Measuring and rendering text with the same arguments yields the same lines.
+///
+/// # Arguments
+///
+/// * `text`: The text to lay out
+/// * `max_size`: The size of the region. Either axis may be infinite
+/// * `wrapping`: How lines wider than the region are broken
+/// * `trimming`: What happens to lines which still don't fit, and to the last visible line when lines are cut off at the bottom
+/// * `line_height`: The distance between the tops of consecutive lines
+/// * `measure`: A function which measures the width of a string
+///
+/// returns: TextLayout
+pub fn layout_text(
+    text: &str,
+    max_size: Point,
+    wrapping: TextWrapping,
+    trimming: TextTrimming,
+    line_height: f32,
+    mut measure: impl FnMut(&str) -> f32,
+) -> TextLayout {
+    let mut lines: Vec<String> = vec![];
+    for paragraph in text.split('\n') {
+        let paragraph = paragraph.strip_suffix('\r').unwrap_or(paragraph);
+        match wrapping {
+            TextWrapping::None => lines.push(paragraph.to_string()),
+            TextWrapping::Word => {
+                lines.append(&mut break_words(paragraph, max_size.x, &mut measure))
+            }
+            TextWrapping::Character => {
+                lines.append(&mut break_characters(paragraph, max_size.x, &mut measure))
+            }
+        }
+    }
+
+    // Lines which would be cut off at the bottom are dropped, but the first line is always kept
+    let visible_lines = if line_height > 0.0 {
+        ((max_size.y / line_height).floor() as usize).max(1)
+    } else {
+        lines.len()
+    };
+    let cut_off = lines.len() > visible_lines;
+    lines.truncate(visible_lines);
+
+    if trimming == TextTrimming::Ellipsis {
+        let last = lines.len() - 1;
+        for (i, line) in lines.iter_mut().enumerate() {
+            if measure(line) > max_size.x || (cut_off && i == last) {
+                *line = trim(line, max_size.x, &mut measure);
+            }
+        }
+    }
+
+    TextLayout {
+        lines: lines
+            .into_iter()
+            .map(|text| TextLine {
+                width: if text.is_empty() { 0.0 } else { measure(&text) },
+                text,
+            })
+            .collect(),
+        line_height,
+    }
+}
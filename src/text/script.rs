@@ -0,0 +1,106 @@
+use crate::text::bidi::is_mark;
+
+/// A writing system, as used to split text into runs
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Script {
+    /// Characters shared between scripts, such as digits, punctuation and whitespace
+    Common,
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Syriac,
+    Thaana,
+    Devanagari,
+    Bengali,
+    Thai,
+    Georgian,
+    Hangul,
+    Hiragana,
+    Katakana,
+    Han,
+
+    /// A script without dedicated handling
+    Other,
+}
+
+/// The code point ranges of each script, excluding characters which are common to all of them
+const SCRIPT_RANGES: &[(u32, u32, Script)] = &[
+    (0x0041, 0x005A, Script::Latin),
+    (0x0061, 0x007A, Script::Latin),
+    (0x00AA, 0x00AA, Script::Latin),
+    (0x00BA, 0x00BA, Script::Latin),
+    (0x00C0, 0x024F, Script::Latin),
+    (0x1E00, 0x1EFF, Script::Latin),
+    (0x0370, 0x03FF, Script::Greek),
+    (0x1F00, 0x1FFF, Script::Greek),
+    (0x0400, 0x052F, Script::Cyrillic),
+    (0x0530, 0x058F, Script::Armenian),
+    (0x0590, 0x05FF, Script::Hebrew),
+    (0xFB1D, 0xFB4F, Script::Hebrew),
+    (0x0600, 0x06FF, Script::Arabic),
+    (0x0750, 0x077F, Script::Arabic),
+    (0x08A0, 0x08FF, Script::Arabic),
+    (0xFB50, 0xFDFF, Script::Arabic),
+    (0xFE70, 0xFEFF, Script::Arabic),
+    (0x0700, 0x074F, Script::Syriac),
+    (0x0780, 0x07BF, Script::Thaana),
+    (0x0900, 0x097F, Script::Devanagari),
+    (0x0980, 0x09FF, Script::Bengali),
+    (0x0E00, 0x0E7F, Script::Thai),
+    (0x10A0, 0x10FF, Script::Georgian),
+    (0x1100, 0x11FF, Script::Hangul),
+    (0xAC00, 0xD7AF, Script::Hangul),
+    (0x3040, 0x309F, Script::Hiragana),
+    (0x30A0, 0x30FF, Script::Katakana),
+    (0x3400, 0x4DBF, Script::Han),
+    (0x4E00, 0x9FFF, Script::Han),
+    (0x20000, 0x2FFFF, Script::Han),
+];
+
+/// Gets the script of a character. Combining marks belong to `Script::Common`, as they inherit their base's script
+pub fn script_of(c: char) -> Script {
+    let code = c as u32;
+    if is_mark(c) {
+        return Script::Common;
+    }
+    if let Some(&(_, _, script)) = SCRIPT_RANGES
+        .iter()
+        .find(|(start, end, _)| code >= *start && code <= *end)
+    {
+        // Arabic digits and punctuation are shared with other scripts
+        let shared_arabic = (0x0660..=0x066D).contains(&code) || code == 0x060C || code == 0x061F;
+        return if shared_arabic {
+            Script::Common
+        } else {
+            script
+        };
+    }
+    if c.is_alphabetic() {
+        Script::Other
+    } else {
+        Script::Common
+    }
+}
+
+/// Resolves the script of each character of a string
+///
+/// Common characters take the script of the preceding character, or of the following one at the start of the string.
+pub fn resolve_scripts(text: &str) -> Vec<Script> {
+    let mut scripts: Vec<Script> = text.chars().map(script_of).collect();
+    let mut previous = scripts
+        .iter()
+        .copied()
+        .find(|x| *x != Script::Common)
+        .unwrap_or(Script::Common);
+    for script in scripts.iter_mut() {
+        if *script == Script::Common {
+            *script = previous;
+        } else {
+            previous = *script;
+        }
+    }
+    scripts
+}
@@ -0,0 +1,455 @@
+use crate::fonts::FontKey;
+use crate::render::TextMeasurer;
+use crate::text::bidi::{self, is_mark, TextDirection};
+use crate::text::script::{self, Script};
+use std::ops::Range;
+
+/// How an Arabic letter connects to its neighbours
+#[derive(Clone, Copy, PartialEq)]
+enum Joining {
+    /// Connects on both sides, with isolated, final, initial and medial forms
+    Dual,
+
+    /// Connects to the preceding letter only, with isolated and final forms
+    Right,
+
+    /// Never connects
+    None,
+
+    /// Connects on both sides without changing shape, like the tatweel
+    Causing,
+}
+
+/// The Arabic letters with presentation forms, along with their isolated form and how they join
+///
+/// The remaining forms follow the isolated one in the order final, initial, medial.
+const ARABIC_FORMS: &[(char, u32, Joining)] = &[
+    ('\u{0621}', 0xFE80, Joining::None),
+    ('\u{0622}', 0xFE81, Joining::Right),
+    ('\u{0623}', 0xFE83, Joining::Right),
+    ('\u{0624}', 0xFE85, Joining::Right),
+    ('\u{0625}', 0xFE87, Joining::Right),
+    ('\u{0626}', 0xFE89, Joining::Dual),
+    ('\u{0627}', 0xFE8D, Joining::Right),
+    ('\u{0628}', 0xFE8F, Joining::Dual),
+    ('\u{0629}', 0xFE93, Joining::Right),
+    ('\u{062A}', 0xFE95, Joining::Dual),
+    ('\u{062B}', 0xFE99, Joining::Dual),
+    ('\u{062C}', 0xFE9D, Joining::Dual),
+    ('\u{062D}', 0xFEA1, Joining::Dual),
+    ('\u{062E}', 0xFEA5, Joining::Dual),
+    ('\u{062F}', 0xFEA9, Joining::Right),
+    ('\u{0630}', 0xFEAB, Joining::Right),
+    ('\u{0631}', 0xFEAD, Joining::Right),
+    ('\u{0632}', 0xFEAF, Joining::Right),
+    ('\u{0633}', 0xFEB1, Joining::Dual),
+    ('\u{0634}', 0xFEB5, Joining::Dual),
+    ('\u{0635}', 0xFEB9, Joining::Dual),
+    ('\u{0636}', 0xFEBD, Joining::Dual),
+    ('\u{0637}', 0xFEC1, Joining::Dual),
+    ('\u{0638}', 0xFEC5, Joining::Dual),
+    ('\u{0639}', 0xFEC9, Joining::Dual),
+    ('\u{063A}', 0xFECD, Joining::Dual),
+    ('\u{0641}', 0xFED1, Joining::Dual),
+    ('\u{0642}', 0xFED5, Joining::Dual),
+    ('\u{0643}', 0xFED9, Joining::Dual),
+    ('\u{0644}', 0xFEDD, Joining::Dual),
+    ('\u{0645}', 0xFEE1, Joining::Dual),
+    ('\u{0646}', 0xFEE5, Joining::Dual),
+    ('\u{0647}', 0xFEE9, Joining::Dual),
+    ('\u{0648}', 0xFEED, Joining::Right),
+    ('\u{0649}', 0xFEEF, Joining::Right),
+    ('\u{064A}', 0xFEF1, Joining::Dual),
+];
+
+/// The alefs which form a ligature with a preceding lam, along with the ligature's isolated form
+const LAM_ALEF_LIGATURES: &[(char, u32)] = &[
+    ('\u{0622}', 0xFEF5),
+    ('\u{0623}', 0xFEF7),
+    ('\u{0625}', 0xFEF9),
+    ('\u{0627}', 0xFEFB),
+];
+
+const LAM: char = '\u{0644}';
+const TATWEEL: char = '\u{0640}';
+
+fn joining(c: char) -> Joining {
+    if c == TATWEEL {
+        return Joining::Causing;
+    }
+    ARABIC_FORMS
+        .iter()
+        .find(|x| x.0 == c)
+        .map(|x| x.2)
+        .unwrap_or(Joining::None)
+}
+
+fn joins_forward(c: char) -> bool {
+    matches!(joining(c), Joining::Dual | Joining::Causing)
+}
+
+fn joins_backward(c: char) -> bool {
+    matches!(
+        joining(c),
+        Joining::Dual | Joining::Right | Joining::Causing
+    )
+}
+
+/// Finds the nearest character which isn't a mark, searching from an index in a direction
+fn neighbour(chars: &[char], from: usize, forward: bool) -> Option<char> {
+    if forward {
+        chars[from + 1..].iter().copied().find(|x| !is_mark(*x))
+    } else {
+        chars[..from].iter().rev().copied().find(|x| !is_mark(*x))
+    }
+}
+
+/// Replaces Arabic letters with the presentation forms matching their position in a word
+///
+/// returns: Vec<(usize, String)> Clusters of (logical character count, shaped text), in logical order
+fn shape_arabic(chars: &[char]) -> Vec<(usize, String)> {
+    let mut clusters: Vec<(usize, String)> = vec![];
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if is_mark(c) {
+            match clusters.last_mut() {
+                Some(last) => {
+                    last.0 += 1;
+                    last.1.push(c);
+                }
+                None => clusters.push((1, c.to_string())),
+            }
+            i += 1;
+            continue;
+        }
+
+        let joins_previous = neighbour(chars, i, false).is_some_and(joins_forward);
+
+        // A lam followed by an alef is replaced by a single ligature, which only has isolated and final forms
+        let alef = if c == LAM {
+            chars[i + 1..]
+                .iter()
+                .position(|x| !is_mark(*x))
+                .map(|x| i + 1 + x)
+                .and_then(|x| {
+                    LAM_ALEF_LIGATURES
+                        .iter()
+                        .find(|y| y.0 == chars[x])
+                        .map(|y| (x, y.1))
+                })
+        } else {
+            None
+        };
+        if let Some((alef_index, isolated)) = alef {
+            let form = isolated + joins_previous as u32;
+            let mut text = char::from_u32(form).unwrap().to_string();
+            text.extend(chars[i + 1..alef_index].iter());
+            clusters.push((alef_index + 1 - i, text));
+            i = alef_index + 1;
+            continue;
+        }
+
+        let shaped = ARABIC_FORMS
+            .iter()
+            .find(|x| x.0 == c)
+            .map(|&(_, isolated, kind)| {
+                let joins_next =
+                    kind == Joining::Dual && neighbour(chars, i, true).is_some_and(joins_backward);
+                let offset = match (kind, joins_previous, joins_next) {
+                    (Joining::None, _, _) => 0,
+                    (_, true, true) => 3,
+                    (_, false, true) => 2,
+                    (_, true, false) => 1,
+                    (_, false, false) => 0,
+                };
+                char::from_u32(isolated + offset).unwrap()
+            });
+        clusters.push((1, shaped.unwrap_or(c).to_string()));
+        i += 1;
+    }
+    clusters
+}
+
+/// Groups characters with the marks following them, so marks stay attached when a run is reversed
+fn cluster_marks(chars: &[char]) -> Vec<(usize, String)> {
+    let mut clusters: Vec<(usize, String)> = vec![];
+    for &c in chars {
+        match clusters.last_mut() {
+            Some(last) if is_mark(c) => {
+                last.0 += 1;
+                last.1.push(c);
+            }
+            _ => clusters.push((1, c.to_string())),
+        }
+    }
+    clusters
+}
+
+/// A sequence of characters which share a direction, script and font
+#[derive(Clone, PartialEq, Debug)]
+pub struct ShapedRun {
+    /// The logical character indices covered by the run
+    pub range: Range<usize>,
+
+    /// The text to draw, shaped and in visual order
+    pub glyphs: String,
+    pub font: FontKey,
+    pub script: Script,
+    pub direction: TextDirection,
+
+    /// The run's left edge, relative to the line's
+    pub x: f32,
+    pub width: f32,
+
+    /// The horizontal advance of each character, in logical order
+    pub advances: Vec<f32>,
+}
+
+impl ShapedRun {
+    /// Gets the position of the boundary before a logical character index inside the run
+    fn edge(&self, index: usize) -> f32 {
+        let offset: f32 = self.advances[..index - self.range.start].iter().sum();
+        match self.direction {
+            TextDirection::LeftToRight => self.x + offset,
+            TextDirection::RightToLeft => self.x + self.width - offset,
+        }
+    }
+}
+
+/// A single line of text split into runs, in visual order
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct ShapedLine {
+    pub runs: Vec<ShapedRun>,
+    pub width: f32,
+
+    /// The base direction of the line
+    pub direction: TextDirection,
+
+    /// The number of characters in the line
+    pub len: usize,
+}
+
+impl ShapedLine {
+    /// Gets the horizontal position of a caret placed before a logical character index
+    ///
+    /// At the boundary of two runs with different directions, the caret sticks to the character at the index.
+    /// An index past the last character places the caret after it.
+    pub fn caret_position(&self, index: usize) -> f32 {
+        let index = index.min(self.len);
+        if let Some(run) = self.runs.iter().find(|x| x.range.contains(&index)) {
+            return run.edge(index);
+        }
+        match self.runs.iter().find(|x| x.range.end == index) {
+            Some(run) => run.edge(index),
+            None if self.direction == TextDirection::RightToLeft => self.width,
+            None => 0.0,
+        }
+    }
+
+    /// Gets the logical character index of the caret position closest to a horizontal position
+    pub fn index_at(&self, x: f32) -> usize {
+        let Some(run) = self
+            .runs
+            .iter()
+            .find(|run| x < run.x + run.width)
+            .or(self.runs.last())
+        else {
+            return 0;
+        };
+        for i in run.range.clone() {
+            let middle = (run.edge(i) + run.edge(i + 1)) / 2.0;
+            let before = match run.direction {
+                TextDirection::LeftToRight => x < middle,
+                TextDirection::RightToLeft => x > middle,
+            };
+            if before {
+                return i;
+            }
+        }
+        run.range.end
+    }
+
+    /// Gets the horizontal spans covered by a logical character range
+    ///
+    /// In mixed-direction text, a contiguous logical range can be visually discontiguous.
+    ///
+    /// returns: Vec<(f32, f32)> The left and right edges of each span, from left to right
+    pub fn selection_ranges(&self, start: usize, end: usize) -> Vec<(f32, f32)> {
+        let mut ranges: Vec<(f32, f32)> = vec![];
+        for run in &self.runs {
+            let from = start.max(run.range.start);
+            let to = end.min(run.range.end);
+            if from >= to {
+                continue;
+            }
+            let (a, b) = (run.edge(from), run.edge(to));
+            let range = (a.min(b), a.max(b));
+            match ranges.last_mut() {
+                Some(last) if (last.1 - range.0).abs() < 0.5 => last.1 = range.1,
+                _ => ranges.push(range),
+            }
+        }
+        ranges
+    }
+}
+
+/// Computes the advances of a run's characters, distributing its measured width so kerning is accounted for
+fn advances(
+    clusters: &[(usize, String)],
+    font: &FontKey,
+    width: f32,
+    measurer: &mut dyn TextMeasurer,
+) -> Vec<f32> {
+    let cluster_widths: Vec<f32> = clusters
+        .iter()
+        .map(|(_, text)| text.chars().map(|c| measurer.glyph_advance(font, c)).sum())
+        .collect();
+    let total: f32 = cluster_widths.iter().sum();
+    let scale = if total > 0.0 { width / total } else { 0.0 };
+    clusters
+        .iter()
+        .zip(cluster_widths)
+        .flat_map(|((len, _), w)| std::iter::repeat_n(w * scale / *len as f32, *len))
+        .collect()
+}
+
+fn shape_run(
+    chars: &[char],
+    range: Range<usize>,
+    level: u8,
+    script: Script,
+    font: FontKey,
+    measurer: &mut dyn TextMeasurer,
+) -> ShapedRun {
+    let run_chars = &chars[range.clone()];
+    let clusters = if script == Script::Arabic {
+        shape_arabic(run_chars)
+    } else {
+        cluster_marks(run_chars)
+    };
+    let direction = if level % 2 == 1 {
+        TextDirection::RightToLeft
+    } else {
+        TextDirection::LeftToRight
+    };
+
+    // SDL draws strings left to right, so right-to-left runs are reversed cluster by cluster
+    let glyphs: String = match direction {
+        TextDirection::LeftToRight => clusters.iter().map(|x| x.1.as_str()).collect(),
+        TextDirection::RightToLeft => clusters.iter().rev().map(|x| x.1.as_str()).collect(),
+    };
+    let width = measurer.measure(&font, &glyphs).x;
+
+    ShapedRun {
+        range,
+        advances: advances(&clusters, &font, width, measurer),
+        glyphs,
+        font,
+        script,
+        direction,
+        x: 0.0,
+        width,
+    }
+}
+
+/// Splits a line of text into runs by direction, script and font, shapes them and orders them visually
+///
+/// Characters missing from the font are taken from the first font in its fallback chain which contains them.
+/// Contextual shaping is only performed for Arabic; other complex scripts are drawn with their nominal glyphs.
+///
+/// # Arguments
+///
+/// * `text`: The line's text, which shouldn't contain newlines
+/// * `font`: The key of the preferred font
+/// * `direction`: The line's base direction, or `None` to detect it from the first strong character
+/// * `measurer`: Measures the glyphs of each run
+///
+/// returns: ShapedLine
+pub fn shape_line(
+    text: &str,
+    font: &FontKey,
+    direction: Option<TextDirection>,
+    measurer: &mut dyn TextMeasurer,
+) -> ShapedLine {
+    let chars: Vec<char> = text.chars().collect();
+    let direction = direction.unwrap_or_else(|| bidi::detect_direction(text));
+    if chars.is_empty() {
+        return ShapedLine {
+            direction,
+            ..Default::default()
+        };
+    }
+
+    // Plain left-to-right text skips segmentation
+    let simple = direction == TextDirection::LeftToRight
+        && text.is_ascii()
+        && chars.iter().all(|c| measurer.has_glyph(font, *c));
+    let (levels, scripts, run_fonts) = if simple {
+        (
+            vec![0; chars.len()],
+            vec![Script::Latin; chars.len()],
+            vec![font.clone(); chars.len()],
+        )
+    } else {
+        let mut run_fonts: Vec<FontKey> = vec![];
+        for &c in &chars {
+            // Marks and whitespace stay in the font of the preceding character when it can draw them
+            let font = match run_fonts.last() {
+                Some(previous)
+                    if (is_mark(c) || c.is_whitespace()) && measurer.has_glyph(previous, c) =>
+                {
+                    previous.clone()
+                }
+                _ => measurer.font_for(font, c),
+            };
+            run_fonts.push(font);
+        }
+        (
+            bidi::resolve_levels(text, direction),
+            script::resolve_scripts(text),
+            run_fonts,
+        )
+    };
+
+    let mut runs = vec![];
+    let mut start = 0;
+    for i in 1..=chars.len() {
+        let splits = i == chars.len()
+            || levels[i] != levels[start]
+            || scripts[i] != scripts[start]
+            || run_fonts[i] != run_fonts[start];
+        if splits {
+            runs.push(shape_run(
+                &chars,
+                start..i,
+                levels[start],
+                scripts[start],
+                run_fonts[start].clone(),
+                measurer,
+            ));
+            start = i;
+        }
+    }
+
+    let run_levels: Vec<u8> = runs
+        .iter()
+        .map(|x: &ShapedRun| levels[x.range.start])
+        .collect();
+    let mut ordered: Vec<ShapedRun> = bidi::reorder(&run_levels)
+        .into_iter()
+        .map(|i| runs[i].clone())
+        .collect();
+    let mut x = 0.0;
+    for run in &mut ordered {
+        run.x = x;
+        x += run.width;
+    }
+
+    ShapedLine {
+        runs: ordered,
+        width: x,
+        direction,
+        len: chars.len(),
+    }
+}
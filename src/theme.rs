@@ -0,0 +1,301 @@
+use crate::core::geo::Thickness;
+use crate::skin::Skin;
+use sdl2::pixels::Color;
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::OnceLock;
+
+/// Describes how a control is being interacted with
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub enum VisualState {
+    /// The control isn't being interacted with
+    #[default]
+    Normal,
+
+    /// The mouse is over the control
+    Hover,
+
+    /// The primary mouse button was pressed over the control and hasn't been released yet
+    Pressed,
+
+    /// The control doesn't accept input
+    Disabled,
+
+    /// The control receives keyboard input
+    Focused,
+}
+
+/// The colors used throughout a theme
+#[derive(Clone, PartialEq, Debug)]
+pub struct Palette {
+    /// The color the window is cleared with
+    pub window_background: Color,
+
+    /// The default text and glyph color
+    pub foreground: Color,
+
+    /// The text and glyph color of disabled controls
+    pub disabled_foreground: Color,
+
+    /// The background of interactive controls
+    pub control_background: Color,
+
+    /// The background of interactive controls while hovered
+    pub control_hover: Color,
+
+    /// The background of interactive controls while pressed
+    pub control_pressed: Color,
+
+    /// The background of disabled interactive controls
+    pub control_disabled: Color,
+
+    /// The color of outlines, grid lines and guides
+    pub border: Color,
+
+    /// The color used to highlight focus and active elements
+    pub accent: Color,
+
+    /// The background of selected items
+    pub selection: Color,
+
+    /// The background of header rows
+    pub header_background: Color,
+
+    /// The background of tracks, such as a progress bar's
+    pub track: Color,
+}
+
+/// The font used to display text
+#[derive(Clone, PartialEq, Debug)]
+pub struct FontSpec {
+    /// The font family's name
+    pub family: String,
+
+    /// The font size, in points
+    pub size: u16,
+}
+
+/// Sizes shared between controls
+#[derive(Clone, PartialEq, Debug)]
+pub struct Metrics {
+    /// The space between a decorated control's outline and its content
+    pub padding: Thickness,
+
+    /// The thickness of outlines
+    pub border_thickness: f32,
+
+    /// The radius of rounded corners
+    pub corner_radius: f32,
+}
+
+/// The colors a control is painted with
+#[derive(Clone, PartialEq, Debug)]
+pub struct Style {
+    pub background: Option<Color>,
+    pub foreground: Color,
+    pub stroke: Option<Color>,
+}
+
+/// A control's styles for each visual state
+#[derive(Clone, PartialEq, Debug)]
+pub struct StateStyles {
+    pub normal: Style,
+    pub hover: Style,
+    pub pressed: Style,
+    pub disabled: Style,
+    pub focused: Style,
+}
+
+impl StateStyles {
+    /// Creates styles which look the same in every visual state
+    pub fn uniform(style: Style) -> StateStyles {
+        StateStyles {
+            normal: style.clone(),
+            hover: style.clone(),
+            pressed: style.clone(),
+            disabled: style.clone(),
+            focused: style,
+        }
+    }
+
+    /// Gets the style for the specified visual state
+    pub fn get(&self, visual_state: VisualState) -> &Style {
+        match visual_state {
+            VisualState::Normal => &self.normal,
+            VisualState::Hover => &self.hover,
+            VisualState::Pressed => &self.pressed,
+            VisualState::Disabled => &self.disabled,
+            VisualState::Focused => &self.focused,
+        }
+    }
+}
+
+/// Describes the appearance of controls
+///
+/// A theme is set on a `Window` and can be overridden for a subtree via `BaseControl::theme`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub palette: Palette,
+    pub font: FontSpec,
+    pub metrics: Metrics,
+
+    /// The styles of each control kind, by name
+    ///
+    /// Controls use the style named after their kind (e.g.: `tree_view`), unless `BaseControl::style` names another one.
+    pub styles: HashMap<String, StateStyles>,
+
+    /// A bitmap skin which, when present, paints controls instead of their styles' background and stroke
+    ///
+    /// Styles without a matching skin part are still painted with their colors.
+    pub skin: Option<Rc<Skin>>,
+}
+
+impl Theme {
+    /// The built-in light theme
+    pub fn light() -> Theme {
+        Theme::from_palette(Palette {
+            window_background: Color::RGB(240, 240, 240),
+            foreground: Color::RGB(0, 0, 0),
+            disabled_foreground: Color::RGB(160, 160, 160),
+            control_background: Color::RGB(225, 225, 225),
+            control_hover: Color::RGB(229, 241, 251),
+            control_pressed: Color::RGB(204, 228, 247),
+            control_disabled: Color::RGB(204, 204, 204),
+            border: Color::RGB(173, 173, 173),
+            accent: Color::RGB(0, 120, 215),
+            selection: Color::RGB(204, 232, 255),
+            header_background: Color::RGB(255, 255, 255),
+            track: Color::RGB(230, 230, 230),
+        })
+    }
+
+    /// The built-in dark theme
+    pub fn dark() -> Theme {
+        Theme::from_palette(Palette {
+            window_background: Color::RGB(32, 32, 32),
+            foreground: Color::RGB(255, 255, 255),
+            disabled_foreground: Color::RGB(109, 109, 109),
+            control_background: Color::RGB(51, 51, 51),
+            control_hover: Color::RGB(69, 69, 69),
+            control_pressed: Color::RGB(102, 102, 102),
+            control_disabled: Color::RGB(43, 43, 43),
+            border: Color::RGB(96, 96, 96),
+            accent: Color::RGB(0, 120, 215),
+            selection: Color::RGB(0, 84, 153),
+            header_background: Color::RGB(43, 43, 43),
+            track: Color::RGB(64, 64, 64),
+        })
+    }
+
+    /// Creates a theme with the default font, metrics and styles derived from a palette
+    pub fn from_palette(palette: Palette) -> Theme {
+        let plain = Style {
+            background: None,
+            foreground: palette.foreground,
+            stroke: None,
+        };
+        let disabled = Style {
+            foreground: palette.disabled_foreground,
+            ..plain.clone()
+        };
+        let interactive = |background: Color, stroke: Color| Style {
+            background: Some(background),
+            foreground: palette.foreground,
+            stroke: Some(stroke),
+        };
+
+        let text_styles = StateStyles {
+            disabled: disabled.clone(),
+            ..StateStyles::uniform(plain.clone())
+        };
+        let list_styles = StateStyles {
+            disabled: Style {
+                stroke: Some(palette.control_disabled),
+                ..disabled.clone()
+            },
+            focused: Style {
+                stroke: Some(palette.accent),
+                ..plain.clone()
+            },
+            ..StateStyles::uniform(Style {
+                stroke: Some(palette.border),
+                ..plain.clone()
+            })
+        };
+        let button_styles = StateStyles {
+            normal: interactive(palette.control_background, palette.border),
+            hover: interactive(palette.control_hover, palette.accent),
+            pressed: interactive(palette.control_pressed, palette.accent),
+            disabled: Style {
+                foreground: palette.disabled_foreground,
+                ..interactive(palette.control_disabled, palette.control_disabled)
+            },
+            focused: interactive(palette.control_background, palette.accent),
+        };
+
+        let mut styles = HashMap::new();
+        styles.insert("label".to_string(), text_styles.clone());
+        styles.insert("rich_text".to_string(), text_styles.clone());
+        styles.insert("stack".to_string(), StateStyles::uniform(plain.clone()));
+        styles.insert("image".to_string(), StateStyles::uniform(plain.clone()));
+        styles.insert(
+            "border".to_string(),
+            StateStyles::uniform(Style {
+                stroke: Some(palette.border),
+                ..plain.clone()
+            }),
+        );
+        styles.insert("button".to_string(), button_styles);
+        styles.insert("tree_view".to_string(), list_styles.clone());
+        styles.insert("data_grid".to_string(), list_styles);
+        styles.insert(
+            "data_grid_header".to_string(),
+            StateStyles::uniform(interactive(palette.header_background, palette.border)),
+        );
+        styles.insert(
+            "progress_bar".to_string(),
+            StateStyles {
+                disabled: Style {
+                    foreground: palette.disabled_foreground,
+                    ..interactive(palette.control_disabled, palette.control_disabled)
+                },
+                ..StateStyles::uniform(interactive(palette.track, palette.border))
+            },
+        );
+
+        Theme {
+            palette,
+            font: FontSpec {
+                family: "Segoe UI".to_string(),
+                size: 16,
+            },
+            metrics: Metrics {
+                padding: Thickness::new(8.0, 4.0, 8.0, 4.0),
+                border_thickness: 1.0,
+                corner_radius: 2.0,
+            },
+            styles,
+            skin: None,
+        }
+    }
+
+    /// Gets the styles with the specified name, or fallback styles if the theme doesn't define them
+    ///
+    /// The theme's stack styles are used first, then the light theme's, so incomplete themes still display.
+    pub fn styles(&self, name: &str) -> &StateStyles {
+        static DEFAULT_STYLES: OnceLock<StateStyles> = OnceLock::new();
+
+        self.styles
+            .get(name)
+            .or_else(|| self.styles.get("stack"))
+            .unwrap_or_else(|| {
+                DEFAULT_STYLES.get_or_init(|| Theme::light().styles["stack"].clone())
+            })
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light()
+    }
+}
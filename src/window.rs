@@ -2,20 +2,49 @@ extern crate sdl2;
 
 use crate::controls::control::{BaseControl, Control};
 use crate::core::geo::Rect;
-use sdl2::event::Event;
+use crate::core::messages::Message;
+use crate::fonts::FontRegistry;
+use crate::overlay::{Overlay, OverlayBuilder};
+use crate::text::TextCacheStats;
+use crate::theme::Theme;
+use sdl2::event::{Event, WindowEvent};
 use sdl2::keyboard::Keycode;
 use sdl2::render::WindowCanvas;
 
-use sdl2::ttf::Sdl2TtfContext;
-use sdl2::EventPump;
+use sdl2::{EventPump, Sdl};
 
 use std::path::Path;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The rate at which animated content is redrawn in `RenderMode::OnDemand`, in frames per second
+pub const ANIMATION_FRAME_RATE: u32 = 60;
+
+/// Decides when a window draws new frames
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum RenderMode {
+    /// Frames are drawn only when the content changes, is interacted with, or is animated
+    ///
+    /// The window sleeps while waiting for events, so idle applications don't use the CPU.
+    #[default]
+    OnDemand,
+
+    /// Frames are drawn continuously, for applications which change every frame, such as games
+    Continuous {
+        /// The highest amount of frames drawn per second
+        frame_rate: u32,
+    },
+}
 
 pub struct WindowBuilder {
     title: String,
     w: u32,
     h: u32,
     content: Control,
+    theme: Theme,
+    fonts: FontRegistry,
+    render_mode: RenderMode,
 }
 
 impl WindowBuilder {
@@ -28,6 +57,9 @@ impl WindowBuilder {
                 base: BaseControl::default(),
                 text: "Hello World!".to_string(),
             },
+            theme: Theme::default(),
+            fonts: FontRegistry::new(),
+            render_mode: RenderMode::default(),
         }
     }
     pub fn title(mut self, title: String) -> WindowBuilder {
@@ -46,79 +78,312 @@ impl WindowBuilder {
         self.content = control;
         self
     }
-    pub fn build(self) -> Window {
-        let sdl_context = sdl2::init().unwrap();
-        let video_subsystem = sdl_context.video().unwrap();
-        let ttf_context = sdl2::ttf::init().map_err(|e| e.to_string()).unwrap();
+    pub fn theme(mut self, theme: Theme) -> WindowBuilder {
+        self.theme = theme;
+        self
+    }
+    /// Sets when the window draws new frames
+    pub fn render_mode(mut self, render_mode: RenderMode) -> WindowBuilder {
+        self.render_mode = render_mode;
+        self
+    }
+    /// Registers a font family from the contents of a font file
+    ///
+    /// # Arguments
+    ///
+    /// * `family`: The family name, as referenced by themes
+    /// * `data`: The contents of a TTF or OTF file
+    ///
+    /// returns: WindowBuilder
+    pub fn font(mut self, family: &str, data: &'static [u8]) -> WindowBuilder {
+        self.fonts.register(family, data);
+        self
+    }
+    /// Registers a font family from font data produced at runtime, such as a downloaded or decompressed file
+    ///
+    /// # Arguments
+    ///
+    /// * `family`: The family name, as referenced by themes
+    /// * `data`: The contents of a TTF or OTF file
+    ///
+    /// returns: WindowBuilder
+    pub fn font_bytes(mut self, family: &str, data: impl Into<Rc<[u8]>>) -> WindowBuilder {
+        self.fonts.register_bytes(family, data);
+        self
+    }
+    /// Registers a font family from a font file
+    ///
+    /// # Arguments
+    ///
+    /// * `family`: The family name, as referenced by themes
+    /// * `path`: The path to a TTF or OTF file
+    ///
+    /// returns: Result<WindowBuilder, String> An error if the file can't be read
+    pub fn font_file<P: AsRef<Path>>(
+        mut self,
+        family: &str,
+        path: P,
+    ) -> Result<WindowBuilder, String> {
+        self.fonts.register_file(family, path)?;
+        Ok(self)
+    }
+    /// Appends a registered family to the chain consulted for characters missing from a font
+    ///
+    /// # Arguments
+    ///
+    /// * `family`: The family name
+    ///
+    /// returns: WindowBuilder
+    pub fn fallback_font(mut self, family: &str) -> WindowBuilder {
+        self.fonts.add_fallback(family);
+        self
+    }
+    /// Creates the window
+    ///
+    /// returns: Result<Window, String> An error if SDL can't be initialized, or if a registered font can't be loaded
+    pub fn build(self) -> Result<Window, String> {
+        let sdl_context = sdl2::init()?;
+        let video_subsystem = sdl_context.video()?;
 
         let window = video_subsystem
             .window(&self.title, self.w, self.h)
             .opengl()
             .resizable()
             .build()
-            .map_err(|e| e.to_string())
-            .unwrap();
+            .map_err(|e| e.to_string())?;
 
-        let canvas = window
-            .into_canvas()
-            .build()
-            .map_err(|e| e.to_string())
-            .unwrap();
-        let event_pump = sdl_context.event_pump().map_err(|e| e.to_string()).unwrap();
+        let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
+        let event_pump = sdl_context.event_pump()?;
 
-        Window {
+        let overlay = OverlayBuilder::new()
+            .content(self.content)
+            .theme(self.theme)
+            .fonts(self.fonts)
+            .build(&canvas)?;
+
+        Ok(Window {
+            sdl_context,
+            overlay,
             event_pump,
             canvas,
-            content: self.content,
-            ttf_context,
-        }
+            render_mode: self.render_mode,
+        })
     }
 }
 
 pub struct Window {
+    sdl_context: Sdl,
+    // Declared before the canvas, so cached textures are destroyed before their renderer
+    overlay: Overlay,
     canvas: WindowCanvas,
     event_pump: EventPump,
-    content: Control,
-    ttf_context: Sdl2TtfContext,
+    render_mode: RenderMode,
 }
 
 impl Window {
+    /// Shows the window and runs its loop until it's closed
+    ///
+    /// In `RenderMode::OnDemand`, the loop sleeps until an event arrives, or until the next animation frame is due,
+    /// and frames are only drawn when something changed.
     pub fn show(&mut self) {
-        // TODO: fix this magic path bullshit
-        let font = self
-            .ttf_context
-            .load_font(Path::new("../../src/skin/segoe.ttf"), 16)
-            .unwrap();
-
-        'running: loop {
-            for event in self.event_pump.poll_iter() {
-                match event {
-                    Event::Quit { .. }
-                    | Event::KeyDown {
-                        keycode: Some(Keycode::Escape),
-                        ..
-                    } => break 'running,
-                    _ => {}
+        let mut last_frame = Instant::now();
+        // The first frame is always drawn
+        let mut redraw = true;
+
+        loop {
+            let frame_start = Instant::now();
+            let animating = self.overlay.content().is_animating();
+
+            let mut events = vec![];
+            if self.render_mode == RenderMode::OnDemand
+                && !redraw
+                && !self.overlay.content().needs_layout()
+            {
+                // Nothing to draw, so sleep until something happens
+                let event = if animating {
+                    self.event_pump
+                        .wait_event_timeout(1000 / ANIMATION_FRAME_RATE)
+                } else {
+                    Some(self.event_pump.wait_event())
+                };
+                events.extend(event);
+            }
+            events.extend(self.event_pump.poll_iter());
+
+            for event in events {
+                let Some(changed) = self.process_event(event) else {
+                    return;
+                };
+                redraw |= changed;
+            }
+
+            let now = Instant::now();
+            self.overlay.update(now - last_frame);
+            last_frame = now;
+            redraw |= animating;
+
+            redraw |= self.layout();
+
+            if redraw || matches!(self.render_mode, RenderMode::Continuous { .. }) {
+                self.render();
+                redraw = false;
+            }
+
+            if let RenderMode::Continuous { frame_rate } = self.render_mode {
+                // Sleep for the rest of the frame, so the frame rate stays under the cap
+                let frame_time = Duration::from_secs(1) / frame_rate.max(1);
+                let elapsed = frame_start.elapsed();
+                if elapsed < frame_time {
+                    thread::sleep(frame_time - elapsed);
                 }
             }
+        }
+    }
 
-            self.content.do_layout(
-                Rect::new(
-                    0.0,
-                    0.0,
-                    self.canvas.window().drawable_size().0 as f32,
-                    self.canvas.window().drawable_size().1 as f32,
-                ),
-                &font,
-            );
-
-            self.content.render(&mut self.canvas);
-            self.canvas.present();
+    /// Routes an SDL event to the content as a message
+    ///
+    /// returns: Option<bool> `None` once the window is asked to quit, otherwise whether the window must be drawn again,
+    /// either because the content changed how it looks or because the window was uncovered or resized
+    fn process_event(&mut self, event: Event) -> Option<bool> {
+        match event {
+            Event::Quit { .. }
+            | Event::KeyDown {
+                keycode: Some(Keycode::Escape),
+                ..
+            } => None,
+            Event::Window { win_event, .. } => Some(matches!(
+                win_event,
+                WindowEvent::Exposed
+                    | WindowEvent::Shown
+                    | WindowEvent::Resized(..)
+                    | WindowEvent::SizeChanged(..)
+                    | WindowEvent::Maximized
+                    | WindowEvent::Restored
+            )),
+            _ => {
+                let modifiers = self.sdl_context.keyboard().mod_state();
+                Some(
+                    Message::from_event(&event, modifiers)
+                        .is_some_and(|x| self.overlay.process_message(x)),
+                )
+            }
         }
     }
 
+    /// Lays out the content to fill the window, if it changed or the window was resized since the last layout
+    ///
+    /// returns: bool Whether the content was laid out
+    fn layout(&mut self) -> bool {
+        let (width, height) = self.canvas.window().drawable_size();
+        self.overlay
+            .layout(Rect::new(0.0, 0.0, width as f32, height as f32))
+    }
+
+    /// Draws the content over the theme's window background, and presents the frame
+    fn render(&mut self) {
+        self.canvas
+            .set_draw_color(self.overlay.theme().palette.window_background);
+        self.canvas.clear();
+        self.overlay.render(&mut self.canvas);
+        self.canvas.present();
+    }
+
     pub fn set_content(&mut self, control: Control) {
-        self.content = control;
-        // FIXME: Invalidate layout!
+        self.overlay.set_content(control);
+    }
+
+    /// The root of the window's control tree
+    pub fn content(&self) -> &Control {
+        self.overlay.content()
+    }
+
+    /// Finds the control with the specified id
+    ///
+    /// returns: Result<Option<&Control>, String> An error if more than one control has the id
+    pub fn find_by_id(&self, id: &str) -> Result<Option<&Control>, String> {
+        self.overlay.content().find_by_id(id)
+    }
+
+    /// Finds the control with the specified id, for modification
+    ///
+    /// The control and its ancestors are laid out again by the next frame, as the control is expected to change. Its
+    /// descendants are too if they inherit a changed theme or flow direction from it.
+    ///
+    /// returns: Result<Option<&mut Control>, String> An error if more than one control has the id
+    pub fn find_by_id_mut(&mut self, id: &str) -> Result<Option<&mut Control>, String> {
+        self.overlay.content_mut().find_by_id_mut(id)
+    }
+
+    /// Inserts a child into the control with the specified id, to be laid out by the next frame
+    ///
+    /// returns: Result<(), String> An error if there isn't exactly one control with the id, or if the index is out of range
+    pub fn insert_child(
+        &mut self,
+        parent_id: &str,
+        index: usize,
+        child: Control,
+    ) -> Result<(), String> {
+        self.overlay
+            .content_mut()
+            .insert_child(parent_id, index, child)
+    }
+
+    /// Removes a child from the control with the specified id
+    ///
+    /// returns: Result<Control, String> The removed child, or an error if there isn't exactly one control with the id, or if the index is out of range
+    pub fn remove_child(&mut self, parent_id: &str, index: usize) -> Result<Control, String> {
+        self.overlay.content_mut().remove_child(parent_id, index)
+    }
+
+    /// Replaces a child of the control with the specified id
+    ///
+    /// returns: Result<Control, String> The replaced child, or an error if there isn't exactly one control with the id, or if the index is out of range
+    pub fn replace_child(
+        &mut self,
+        parent_id: &str,
+        index: usize,
+        child: Control,
+    ) -> Result<Control, String> {
+        self.overlay
+            .content_mut()
+            .replace_child(parent_id, index, child)
+    }
+
+    /// Changes properties of the control with the specified id, to be laid out by the next frame
+    ///
+    /// returns: Result<(), String> An error if there isn't exactly one control with the id
+    pub fn modify(&mut self, id: &str, change: impl FnOnce(&mut Control)) -> Result<(), String> {
+        self.overlay.content_mut().modify(id, change)
+    }
+
+    /// Finds every control which matches a predicate, in depth-first order
+    pub fn find_all(&self, predicate: &dyn Fn(&Control) -> bool) -> Vec<&Control> {
+        self.overlay.content().find_all(predicate)
+    }
+
+    /// Gets the ancestors of the control with the specified id, from its parent up to the root
+    ///
+    /// returns: Result<Option<Vec<&Control>>, String> `None` if no control has the id, or an error if more than one does
+    pub fn ancestors_of(&self, id: &str) -> Result<Option<Vec<&Control>>, String> {
+        self.overlay.content().ancestors_of(id)
+    }
+
+    /// Sets when the window draws new frames
+    pub fn set_render_mode(&mut self, render_mode: RenderMode) {
+        self.render_mode = render_mode;
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.overlay.set_theme(theme);
+    }
+
+    /// Gets the counters of the cache which holds rendered text, for profiling
+    pub fn text_cache_stats(&self) -> TextCacheStats {
+        self.overlay.text_cache_stats()
+    }
+
+    /// Changes the number of rendered strings kept between frames
+    pub fn set_text_cache_capacity(&mut self, capacity: usize) {
+        self.overlay.set_text_cache_capacity(capacity);
     }
 }
@@ -0,0 +1,74 @@
+use ugui_r_rs::text::bidi::{detect_direction, reorder, resolve_levels, TextDirection};
+
+// Visual order of a string's characters, as the characters themselves
+fn visual(text: &str, direction: TextDirection) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    reorder(&resolve_levels(text, direction))
+        .into_iter()
+        .map(|i| chars[i])
+        .collect()
+}
+
+#[test]
+fn detects_the_first_strong_direction() {
+    assert_eq!(detect_direction("123 שלום abc"), TextDirection::RightToLeft);
+    assert_eq!(detect_direction("(abc) שלום"), TextDirection::LeftToRight);
+    assert_eq!(detect_direction("مرحبا"), TextDirection::RightToLeft);
+    assert_eq!(detect_direction("123 !"), TextDirection::LeftToRight);
+}
+
+#[test]
+fn resolves_hebrew_and_numbers_in_left_to_right_text() {
+    let text = "abc אבג 123";
+    assert_eq!(
+        resolve_levels(text, TextDirection::LeftToRight),
+        vec![0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2]
+    );
+    // Numbers inside the right-to-left run keep their own order
+    assert_eq!(
+        reorder(&resolve_levels(text, TextDirection::LeftToRight)),
+        vec![0, 1, 2, 3, 8, 9, 10, 7, 6, 5, 4]
+    );
+    assert_eq!(visual(text, TextDirection::LeftToRight), "abc 123 גבא");
+}
+
+#[test]
+fn resolves_arabic_numbers_in_right_to_left_text() {
+    // Digits following Arabic letters become Arabic numbers, and the separator between them joins them
+    let text = "عدد 1,5";
+    assert_eq!(
+        resolve_levels(text, TextDirection::RightToLeft),
+        vec![1, 1, 1, 1, 2, 2, 2]
+    );
+    assert_eq!(visual(text, TextDirection::RightToLeft), "1,5 ددع");
+}
+
+#[test]
+fn resolves_left_to_right_text_in_right_to_left_paragraphs() {
+    let text = "שלום world 2024!";
+    let levels = resolve_levels(text, TextDirection::RightToLeft);
+    assert_eq!(&levels[..5], &[1, 1, 1, 1, 1]);
+    assert!(levels[5..15].iter().all(|x| *x == 2));
+    // The trailing neutral sits between a left-to-right run and the paragraph's end, so it takes the paragraph level
+    assert_eq!(levels[15], 1);
+    assert_eq!(visual(text, TextDirection::RightToLeft), "!world 2024 םולש");
+}
+
+#[test]
+fn resets_trailing_whitespace_to_the_paragraph_level() {
+    assert_eq!(
+        resolve_levels("אב  ", TextDirection::LeftToRight),
+        vec![1, 1, 0, 0]
+    );
+    assert_eq!(resolve_levels("", TextDirection::RightToLeft), vec![]);
+    assert_eq!(reorder(&[]), vec![]);
+}
+
+#[test]
+fn marks_take_the_level_of_their_base() {
+    // Hebrew point hiriq after a letter
+    assert_eq!(
+        resolve_levels("a\u{05D1}\u{05B4}", TextDirection::LeftToRight),
+        vec![0, 1, 1]
+    );
+}
@@ -0,0 +1,58 @@
+use ugui_r_rs::controls::control::Control::{self, Label, Stack};
+use ugui_r_rs::controls::control::{BaseControl, Orientation};
+use ugui_r_rs::core::geo::Alignment;
+
+fn label(text: &str, h_align: Alignment, v_align: Alignment) -> Control {
+    Label {
+        base: BaseControl {
+            h_align,
+            v_align,
+            ..Default::default()
+        },
+        text: text.to_string(),
+    }
+}
+
+/// The tree shown by the `layout` example: every alignment, inside a vertical and a horizontal stack
+pub fn layout_example() -> Control {
+    let alignments = [
+        ("Start", Alignment::Start),
+        ("Center", Alignment::Center),
+        ("End", Alignment::End),
+        ("Fill", Alignment::Fill),
+    ];
+    Stack {
+        orientation: Orientation::Horizontal,
+        base: BaseControl {
+            h_align: Alignment::Center,
+            v_align: Alignment::Center,
+            children: vec![
+                Stack {
+                    orientation: Orientation::Vertical,
+                    base: BaseControl {
+                        h_align: Alignment::Fill,
+                        v_align: Alignment::Fill,
+                        children: alignments
+                            .iter()
+                            .map(|(text, align)| label(text, *align, Alignment::default()))
+                            .collect(),
+                        ..Default::default()
+                    },
+                },
+                Stack {
+                    orientation: Orientation::Horizontal,
+                    base: BaseControl {
+                        h_align: Alignment::Fill,
+                        v_align: Alignment::Fill,
+                        children: alignments
+                            .iter()
+                            .map(|(text, align)| label(text, Alignment::default(), *align))
+                            .collect(),
+                        ..Default::default()
+                    },
+                },
+            ],
+            ..Default::default()
+        },
+    }
+}
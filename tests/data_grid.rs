@@ -0,0 +1,132 @@
+use sdl2::keyboard::{Keycode, Mod};
+use ugui_r_rs::controls::control::Control::{self, DataGrid};
+use ugui_r_rs::controls::control::{BaseControl, SelectionMode};
+use ugui_r_rs::controls::data_grid::{DataGridColumn, DataGridState, SortDirection};
+use ugui_r_rs::core::geo::{Point, Rect};
+use ugui_r_rs::core::messages::Message;
+use ugui_r_rs::testing::driver::TestDriver;
+
+fn grid(row_count: usize, sort: Option<(usize, SortDirection)>) -> Control {
+    let mut state = DataGridState::default();
+    state.sort = sort;
+    DataGrid {
+        base: BaseControl {
+            id: Some("grid".to_string()),
+            ..Default::default()
+        },
+        columns: vec![
+            DataGridColumn::new("Name", 300.0),
+            DataGridColumn::new("Size", 300.0),
+        ],
+        rows: (0..row_count)
+            .map(|i| vec![format!("File {}", i), i.to_string()])
+            .collect(),
+        frozen_columns: 1,
+        row_height: 20.0,
+        selection_mode: SelectionMode::Single,
+        state,
+    }
+}
+
+fn state(driver: &TestDriver) -> &DataGridState {
+    let Some(DataGrid { state, .. }) = driver.find_by_id("grid") else {
+        panic!("Expected a data grid");
+    };
+    state
+}
+
+#[test]
+fn large_grids_fit_the_available_space_and_scroll() {
+    let mut driver = TestDriver::new(grid(100, None), 400.0, 300.0);
+    assert_eq!(
+        driver
+            .find_by_id("grid")
+            .unwrap()
+            .get_base()
+            .computed_bounds,
+        Rect::new(0.0, 0.0, 400.0, 300.0)
+    );
+
+    driver.mouse_move(Point { x: 100.0, y: 100.0 });
+    driver.scroll(Point { x: 1.0, y: -1.0 });
+    assert_eq!(state(&driver).scroll, Point { x: 60.0, y: 60.0 });
+}
+
+#[test]
+fn initial_sort_is_applied_by_layout() {
+    let driver = TestDriver::new(grid(3, Some((1, SortDirection::Descending))), 400.0, 300.0);
+    let order = (0..3).map(|i| state(&driver).row_at(i)).collect::<Vec<_>>();
+    assert_eq!(order, vec![2, 1, 0]);
+}
+
+#[test]
+fn changed_rows_are_sorted_again() {
+    let mut driver = TestDriver::new(grid(3, Some((1, SortDirection::Ascending))), 400.0, 300.0);
+    driver
+        .content_mut()
+        .modify("grid", |x| {
+            if let DataGrid { rows, .. } = x {
+                rows[0][1] = "10".to_string();
+            }
+        })
+        .unwrap();
+    driver.step();
+    let order = (0..3).map(|i| state(&driver).row_at(i)).collect::<Vec<_>>();
+    assert_eq!(order, vec![1, 2, 0]);
+}
+
+#[test]
+fn only_scrolling_and_selection_need_redrawing() {
+    let mut driver = TestDriver::new(grid(100, None), 400.0, 300.0);
+    assert!(driver.send(Message::MouseMove(Point { x: 100.0, y: 100.0 })));
+    assert!(!driver.send(Message::MouseMove(Point { x: 110.0, y: 100.0 })));
+
+    // Scrolling past the top changes nothing
+    assert!(!driver.send(Message::MouseWheel(Point { x: 0.0, y: 1.0 })));
+    assert!(driver.send(Message::MouseWheel(Point { x: 0.0, y: -1.0 })));
+
+    assert!(driver.send(Message::MouseDown(Point { x: 110.0, y: 100.0 }, Mod::NOMOD)));
+    assert_eq!(state(&driver).selected.len(), 1);
+}
+
+fn click_row(driver: &mut TestDriver, display_index: usize) {
+    driver.click(Point {
+        x: 100.0,
+        y: 30.0 + display_index as f32 * 20.0,
+    });
+}
+
+#[test]
+fn shift_click_selects_the_range_from_the_anchor() {
+    let mut content = grid(50_000, Some((1, SortDirection::Descending)));
+    if let DataGrid { selection_mode, .. } = &mut content {
+        *selection_mode = SelectionMode::Multiple;
+    }
+    let mut driver = TestDriver::new(content, 400.0, 300.0);
+
+    // Rows are shown in descending order, so display position 2 holds row 49997
+    click_row(&mut driver, 2);
+    driver.set_modifiers(Mod::LSHIFTMOD);
+    click_row(&mut driver, 6);
+    assert!(state(&driver)
+        .selected
+        .iter()
+        .eq(&[49993, 49994, 49995, 49996, 49997]));
+
+    // The anchor stays put, so the range shrinks and can flip to the other side
+    click_row(&mut driver, 4);
+    assert!(state(&driver).selected.iter().eq(&[49995, 49996, 49997]));
+    click_row(&mut driver, 0);
+    assert!(state(&driver).selected.iter().eq(&[49997, 49998, 49999]));
+
+    // Ctrl-clicking moves the anchor
+    driver.set_modifiers(Mod::LCTRLMOD);
+    click_row(&mut driver, 5);
+    driver.set_modifiers(Mod::LSHIFTMOD);
+    click_row(&mut driver, 7);
+    assert!(state(&driver).selected.iter().eq(&[49992, 49993, 49994]));
+
+    // Extending the range to the end of a large grid
+    driver.key_down(Keycode::End);
+    assert_eq!(state(&driver).selected.len(), 49_995);
+}
@@ -0,0 +1,335 @@
+use sdl2::event::Event;
+use sdl2::keyboard::Mod;
+use sdl2::mouse::MouseButton;
+use std::cell::RefCell;
+use std::time::Duration;
+use ugui_r_rs::controls::control::Control::{self, Label, ProgressBar, RichText, Stack};
+use ugui_r_rs::controls::control::{BaseControl, Orientation};
+use ugui_r_rs::controls::rich_text::{parse_markup, LinkHandler, RichTextState};
+use ugui_r_rs::core::geo::Point;
+use ugui_r_rs::core::messages::Message;
+use ugui_r_rs::testing::driver::TestDriver;
+use ugui_r_rs::theme::VisualState;
+
+thread_local! {
+    static EVENTS: RefCell<Vec<String>> = const { RefCell::new(vec![]) };
+}
+
+fn record(event: String) {
+    EVENTS.with(|x| x.borrow_mut().push(event));
+}
+
+fn events() -> Vec<String> {
+    EVENTS.with(|x| x.take())
+}
+
+fn label(id: &str, text: &str) -> Control {
+    Label {
+        base: BaseControl {
+            id: Some(id.to_string()),
+            ..Default::default()
+        },
+        text: text.to_string(),
+    }
+}
+
+fn form() -> Control {
+    Stack {
+        orientation: Orientation::Vertical,
+        base: BaseControl {
+            children: vec![
+                label("save", "Save"),
+                Label {
+                    base: BaseControl {
+                        id: Some("name".to_string()),
+                        on_message: |message| {
+                            if let Message::TextInput(text) = message {
+                                record(format!("typed {}", text));
+                            }
+                        },
+                        ..Default::default()
+                    },
+                    text: "Name".to_string(),
+                },
+                RichText {
+                    base: BaseControl {
+                        id: Some("help".to_string()),
+                        ..Default::default()
+                    },
+                    spans: parse_markup("See [link=docs]the docs[/link]").unwrap(),
+                    on_link: LinkHandler(|target| record(format!("link {}", target))),
+                    state: RichTextState::default(),
+                },
+                ProgressBar {
+                    base: BaseControl {
+                        id: Some("progress".to_string()),
+                        ..Default::default()
+                    },
+                    minimum: 0.0,
+                    maximum: 1.0,
+                    value: 0.0,
+                    indeterminate: true,
+                    show_percentage: false,
+                    state: Default::default(),
+                },
+            ],
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn finds_controls_by_id_and_text() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0);
+    assert_eq!(driver.text_of("save").as_deref(), Some("Save"));
+    assert_eq!(driver.text_of("help").as_deref(), Some("See the docs"));
+    assert!(driver.find_by_text("Name").is_some());
+    assert!(driver.find_by_id("missing").is_none());
+    assert!(driver.click_text("Missing").is_err());
+}
+
+#[test]
+fn clicking_tracks_visual_state() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0);
+    let center = driver.center_of("save").unwrap();
+
+    driver.mouse_move(center);
+    assert_eq!(
+        driver.find_by_id("save").unwrap().visual_state(),
+        VisualState::Hover
+    );
+    driver.mouse_down(center);
+    assert_eq!(
+        driver.find_by_id("save").unwrap().visual_state(),
+        VisualState::Pressed
+    );
+    driver.mouse_up(center);
+    assert_eq!(
+        driver.find_by_id("save").unwrap().visual_state(),
+        VisualState::Hover
+    );
+}
+
+#[test]
+fn typing_reaches_controls() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0);
+    driver.click_id("name").unwrap();
+    driver.type_text("Ada");
+    assert_eq!(events(), vec!["typed Ada"]);
+}
+
+#[test]
+fn clicking_a_link_invokes_the_handler() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0);
+    let bounds = driver
+        .find_by_id("help")
+        .unwrap()
+        .get_base()
+        .computed_bounds;
+
+    // The link ends the text, so the control's right edge lies inside it
+    driver.click(Point {
+        x: bounds.x + bounds.w - 4.0,
+        y: bounds.y + bounds.h / 2.0,
+    });
+    assert_eq!(events(), vec!["link docs"]);
+}
+
+#[test]
+fn links_follow_only_when_released_over_them() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0);
+    let bounds = driver
+        .find_by_id("help")
+        .unwrap()
+        .get_base()
+        .computed_bounds;
+    let link = Point {
+        x: bounds.x + bounds.w - 4.0,
+        y: bounds.y + bounds.h / 2.0,
+    };
+
+    driver.mouse_move(link);
+    let Some(RichText { state, .. }) = driver.find_by_id("help") else {
+        panic!("Expected rich text");
+    };
+    assert_eq!(state.hovered_span, Some(1));
+
+    // Pressing alone doesn't follow the link, and releasing elsewhere cancels it
+    driver.mouse_down(link);
+    assert!(events().is_empty());
+    driver.mouse_up(Point {
+        x: bounds.x + 4.0,
+        ..link
+    });
+    assert!(events().is_empty());
+
+    // Presses starting outside the link don't follow it either
+    driver.mouse_down(Point {
+        x: bounds.x + 4.0,
+        ..link
+    });
+    driver.mouse_up(link);
+    assert!(events().is_empty());
+}
+
+#[test]
+fn replacing_spans_forgets_the_pressed_link() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0);
+    let bounds = driver
+        .find_by_id("help")
+        .unwrap()
+        .get_base()
+        .computed_bounds;
+    let link = Point {
+        x: bounds.x + bounds.w - 4.0,
+        y: bounds.y + bounds.h / 2.0,
+    };
+    driver.mouse_move(link);
+    driver.mouse_down(link);
+
+    // The release is routed before the next layout, while the old layout still places the link there
+    driver
+        .content_mut()
+        .modify("help", |x| {
+            if let RichText { spans, .. } = x {
+                *spans = parse_markup("Help").unwrap();
+            }
+        })
+        .unwrap();
+    let Some(RichText { state, .. }) = driver.find_by_id("help") else {
+        panic!("Expected rich text");
+    };
+    assert_eq!((state.hovered_span, state.pressed_span), (None, None));
+    driver.mouse_up(link);
+    assert!(events().is_empty());
+}
+
+#[test]
+fn only_visual_changes_need_redrawing() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0);
+    let center = driver.center_of("save").unwrap();
+
+    assert!(driver.send(Message::MouseMove(center)));
+    assert!(!driver.send(Message::MouseMove(Point {
+        x: center.x + 1.0,
+        ..center
+    })));
+    assert!(driver.send(Message::MouseDown(center, Mod::NOMOD)));
+    assert!(driver.send(Message::MouseUp(center)));
+
+    // Typing only reaches the handler, and wheeling over labels doesn't scroll anything
+    assert!(!driver.send(Message::TextInput("a".to_string())));
+    assert!(!driver.send(Message::MouseWheel(Point { x: 0.0, y: 1.0 })));
+    events();
+
+    // Hovering a link highlights it
+    let bounds = driver
+        .find_by_id("help")
+        .unwrap()
+        .get_base()
+        .computed_bounds;
+    assert!(driver.send(Message::MouseMove(Point {
+        x: bounds.x + bounds.w - 4.0,
+        y: bounds.y + bounds.h / 2.0,
+    })));
+}
+
+#[test]
+fn frames_advance_animations_deterministically() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0).frame_time(Duration::from_millis(100));
+    driver.step_frames(3);
+    let Some(ProgressBar { state, .. }) = driver.find_by_id("progress") else {
+        panic!("Expected a progress bar");
+    };
+    assert!((state.phase - 0.2).abs() < 1e-5);
+}
+
+#[test]
+fn indeterminate_progress_is_animated() {
+    let mut driver = TestDriver::new(form(), 400.0, 300.0);
+    assert!(driver.content().is_animating());
+
+    driver
+        .content_mut()
+        .modify("progress", |x| {
+            if let ProgressBar { indeterminate, .. } = x {
+                *indeterminate = false;
+            }
+        })
+        .unwrap();
+    assert!(!driver.content().is_animating());
+}
+
+#[test]
+fn queries_the_tree_by_id() {
+    let mut content = form();
+    assert_eq!(
+        content
+            .ancestors_of("save")
+            .unwrap()
+            .unwrap()
+            .iter()
+            .map(|x| x.kind())
+            .collect::<Vec<_>>(),
+        vec!["stack"]
+    );
+    assert_eq!(content.find_all(&|x| x.kind() == "label").len(), 2);
+
+    if let Some(Label { text, .. }) = content.find_by_id_mut("save").unwrap() {
+        *text = "Saved".to_string();
+    }
+    let driver = TestDriver::new(content, 400.0, 300.0);
+    assert_eq!(driver.text_of("save").as_deref(), Some("Saved"));
+}
+
+#[test]
+fn duplicate_ids_are_reported() {
+    let content = Stack {
+        orientation: Orientation::Vertical,
+        base: BaseControl {
+            children: vec![label("title", "One"), label("title", "Two")],
+            ..Default::default()
+        },
+    };
+    assert!(content.find_by_id("title").is_err());
+    assert!(content.ancestors_of("title").is_err());
+    assert!(content.find_by_id("other").unwrap().is_none());
+}
+
+#[test]
+fn sdl_events_become_messages() {
+    let down = Event::MouseButtonDown {
+        timestamp: 0,
+        window_id: 0,
+        which: 0,
+        mouse_btn: MouseButton::Left,
+        clicks: 1,
+        x: 10,
+        y: 20,
+    };
+    assert!(matches!(
+        Message::from_event(&down, Mod::LCTRLMOD),
+        Some(Message::MouseDown(Point { x, y }, Mod::LCTRLMOD)) if x == 10.0 && y == 20.0
+    ));
+
+    let right = Event::MouseButtonDown {
+        timestamp: 0,
+        window_id: 0,
+        which: 0,
+        mouse_btn: MouseButton::Right,
+        clicks: 1,
+        x: 10,
+        y: 20,
+    };
+    assert!(Message::from_event(&right, Mod::NOMOD).is_none());
+
+    let typed = Event::TextInput {
+        timestamp: 0,
+        window_id: 0,
+        text: "é".to_string(),
+    };
+    assert!(matches!(
+        Message::from_event(&typed, Mod::NOMOD),
+        Some(Message::TextInput(text)) if text == "é"
+    ));
+}
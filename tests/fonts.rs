@@ -0,0 +1,19 @@
+use ugui_r_rs::fonts::{FontRegistry, DEFAULT_FONT_FAMILY};
+
+#[test]
+fn registers_fonts_produced_at_runtime() {
+    let mut fonts = FontRegistry::new();
+    let default = fonts.get(DEFAULT_FONT_FAMILY).to_vec();
+
+    // A copy stands in for a downloaded font, and is owned by the registry rather than leaked
+    fonts.register_bytes("Downloaded", default.clone());
+    assert_eq!(fonts.get("Downloaded"), default.as_slice());
+    assert!(fonts.families().any(|x| x == "Downloaded"));
+
+    // Registering the family again replaces its data
+    fonts.register_bytes("Downloaded", vec![1, 2, 3]);
+    assert_eq!(fonts.get("Downloaded"), &[1, 2, 3]);
+
+    // Unregistered families get the default font's data
+    assert_eq!(fonts.get("Missing"), default.as_slice());
+}
@@ -0,0 +1,97 @@
+use sdl2::pixels::Color;
+use ugui_r_rs::render::framebuffer::Framebuffer;
+
+/// A framebuffer whose pixels all differ, with translucent pixels and an odd width
+fn pattern() -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(5, 3);
+    for y in 0..3 {
+        for x in 0..5 {
+            framebuffer.blend_pixel(x, y, Color::RGBA((x * 50) as u8, (y * 100) as u8, 200, 255));
+        }
+    }
+    framebuffer.blend_pixel(4, 2, Color::RGBA(0, 0, 0, 0));
+    framebuffer
+}
+
+fn u32_be(data: &[u8]) -> u32 {
+    u32::from_be_bytes([data[0], data[1], data[2], data[3]])
+}
+
+#[test]
+fn bmp_round_trips() {
+    let mut framebuffer = pattern();
+    framebuffer.blend_pixel(0, 0, Color::RGBA(255, 0, 0, 128));
+    let decoded = Framebuffer::decode_bmp(&framebuffer.encode_bmp()).unwrap();
+    assert_eq!(decoded, framebuffer);
+
+    let empty = Framebuffer::new(0, 0);
+    assert_eq!(Framebuffer::decode_bmp(&empty.encode_bmp()).unwrap(), empty);
+}
+
+#[test]
+fn decodes_padded_24_bit_bmps() {
+    // A 1x2 image, stored bottom-up with each 3 byte row padded to 4 bytes
+    let mut bmp = vec![];
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&62u32.to_le_bytes());
+    bmp.extend_from_slice(&[0; 4]);
+    bmp.extend_from_slice(&54u32.to_le_bytes());
+    bmp.extend_from_slice(&40u32.to_le_bytes());
+    bmp.extend_from_slice(&1i32.to_le_bytes());
+    bmp.extend_from_slice(&2i32.to_le_bytes());
+    bmp.extend_from_slice(&1u16.to_le_bytes());
+    bmp.extend_from_slice(&24u16.to_le_bytes());
+    bmp.extend_from_slice(&[0; 24]);
+    bmp.extend_from_slice(&[255, 0, 0, 0, 0, 0, 255, 0]);
+
+    let decoded = Framebuffer::decode_bmp(&bmp).unwrap();
+    assert_eq!(decoded.pixel(0, 0), Color::RGBA(255, 0, 0, 255));
+    assert_eq!(decoded.pixel(0, 1), Color::RGBA(0, 0, 255, 255));
+
+    assert!(Framebuffer::decode_bmp(&bmp[..60]).is_err());
+    assert!(Framebuffer::decode_bmp(b"PNG").is_err());
+}
+
+#[test]
+fn png_round_trips() {
+    let framebuffer = pattern();
+    let png = framebuffer.encode_png();
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+    // Walk the chunks, collecting the image data
+    let mut chunks = vec![];
+    let mut i = 8;
+    while i < png.len() {
+        let length = u32_be(&png[i..]) as usize;
+        chunks.push((&png[i + 4..i + 8], &png[i + 8..i + 8 + length]));
+        i += 12 + length;
+    }
+    assert_eq!(i, png.len());
+    let kinds: Vec<&[u8]> = chunks.iter().map(|x| x.0).collect();
+    assert_eq!(kinds, vec![b"IHDR" as &[u8], b"IDAT", b"IEND"]);
+    let header = chunks[0].1;
+    assert_eq!((u32_be(header), u32_be(&header[4..])), (5, 3));
+    assert_eq!(&header[8..], &[8, 6, 0, 0, 0]);
+
+    // The zlib stream is made of stored blocks, so the scanlines can be read back directly
+    let stream = chunks[1].1;
+    let mut scanlines = vec![];
+    let mut i = 2;
+    loop {
+        let last = stream[i] & 1 == 1;
+        let length = u16::from_le_bytes([stream[i + 1], stream[i + 2]]) as usize;
+        scanlines.extend_from_slice(&stream[i + 5..i + 5 + length]);
+        i += 5 + length;
+        if last {
+            break;
+        }
+    }
+    assert_eq!(i + 4, stream.len());
+
+    let mut pixels = vec![];
+    for row in scanlines.chunks(5 * 4 + 1) {
+        assert_eq!(row[0], 0);
+        pixels.extend_from_slice(&row[1..]);
+    }
+    assert_eq!(pixels, framebuffer.pixels());
+}
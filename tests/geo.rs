@@ -0,0 +1,65 @@
+use ugui_r_rs::core::geo::Rect;
+use ugui_r_rs::render::ClipStack;
+
+#[test]
+fn intersects_overlapping_rects() {
+    let a = Rect::new(0.0, 0.0, 100.0, 50.0);
+    let b = Rect::new(60.0, 20.0, 100.0, 100.0);
+    assert_eq!(a.intersect(b), Rect::new(60.0, 20.0, 40.0, 30.0));
+    assert_eq!(b.intersect(a), a.intersect(b));
+}
+
+#[test]
+fn intersects_contained_rects() {
+    let outer = Rect::new(10.0, 10.0, 200.0, 200.0);
+    let inner = Rect::new(50.0, 60.0, 20.0, 30.0);
+    assert_eq!(outer.intersect(inner), inner);
+    assert_eq!(inner.intersect(outer), inner);
+    assert_eq!(outer.intersect(outer), outer);
+}
+
+#[test]
+fn touching_rects_share_an_empty_edge() {
+    let left = Rect::new(0.0, 0.0, 50.0, 50.0);
+    let right = Rect::new(50.0, 10.0, 50.0, 50.0);
+    assert_eq!(left.intersect(right), Rect::new(50.0, 10.0, 0.0, 40.0));
+
+    let below = Rect::new(0.0, 50.0, 50.0, 50.0);
+    assert_eq!(left.intersect(below), Rect::new(0.0, 50.0, 50.0, 0.0));
+}
+
+#[test]
+fn disjoint_rects_intersect_in_an_empty_rect() {
+    let a = Rect::new(0.0, 0.0, 10.0, 10.0);
+    let b = Rect::new(100.0, 200.0, 10.0, 10.0);
+    for rect in [a.intersect(b), b.intersect(a)] {
+        assert_eq!(rect.w, 0.0);
+        assert_eq!(rect.h, 0.0);
+    }
+
+    // Rects overlapping on one axis only are still empty, without a negative size on the other
+    let c = Rect::new(5.0, 40.0, 10.0, 10.0);
+    let rect = a.intersect(c);
+    assert_eq!((rect.w, rect.h), (5.0, 0.0));
+}
+
+#[test]
+fn nested_clips_intersect() {
+    let mut stack = ClipStack::new();
+    assert_eq!(stack.current(), None);
+
+    stack.push(Rect::new(0.0, 0.0, 100.0, 100.0));
+    stack.push(Rect::new(50.0, 50.0, 100.0, 100.0));
+    assert_eq!(stack.current(), Some(Rect::new(50.0, 50.0, 50.0, 50.0)));
+
+    // A clip outside the current one hides everything drawn inside it
+    stack.push(Rect::new(200.0, 0.0, 10.0, 10.0));
+    let clip = stack.current().unwrap();
+    assert_eq!((clip.w, clip.h), (0.0, 0.0));
+
+    stack.pop();
+    stack.pop();
+    assert_eq!(stack.current(), Some(Rect::new(0.0, 0.0, 100.0, 100.0)));
+    stack.pop();
+    assert_eq!(stack.current(), None);
+}
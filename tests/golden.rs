@@ -0,0 +1,73 @@
+use sdl2::pixels::Color;
+use ugui_r_rs::controls::control::Control::{self, Border, Image, ProgressBar, Stack};
+use ugui_r_rs::controls::control::{BaseControl, Orientation};
+use ugui_r_rs::controls::image::{ImageData, Stretch};
+use ugui_r_rs::core::geo::{Alignment, Thickness};
+use ugui_r_rs::testing::golden::GoldenTest;
+
+/// Borders, an image and a progress bar, without any text, so the reference doesn't depend on the font rasterizer
+fn shapes() -> Control {
+    let gradient = ImageData::from_rgba(
+        16,
+        8,
+        (0..8)
+            .flat_map(|y| (0..16).flat_map(move |x| [x * 16, y * 32, 128, 255]))
+            .collect(),
+    )
+    .unwrap();
+    Stack {
+        orientation: Orientation::Horizontal,
+        base: BaseControl {
+            h_align: Alignment::Center,
+            v_align: Alignment::Center,
+            children: vec![
+                Border {
+                    base: BaseControl {
+                        children: vec![Image {
+                            base: BaseControl::default(),
+                            source: gradient,
+                            stretch: Stretch::None,
+                        }],
+                        ..Default::default()
+                    },
+                    background: Some(Color::RGB(250, 240, 200)),
+                    stroke: Some(Color::RGB(200, 80, 40)),
+                    stroke_thickness: Some(2.0),
+                    corner_radius: Some(6.0),
+                    padding: Some(Thickness::new(8.0, 4.0, 8.0, 4.0)),
+                },
+                ProgressBar {
+                    base: BaseControl {
+                        v_align: Alignment::Center,
+                        ..Default::default()
+                    },
+                    minimum: 0.0,
+                    maximum: 10.0,
+                    value: 4.0,
+                    indeterminate: false,
+                    show_percentage: false,
+                    state: Default::default(),
+                },
+                Border {
+                    base: BaseControl::default(),
+                    background: Some(Color::RGBA(40, 120, 200, 128)),
+                    stroke: None,
+                    stroke_thickness: Some(0.0),
+                    corner_radius: Some(0.0),
+                    padding: Some(Thickness::uniform(12.0)),
+                },
+            ],
+            ..Default::default()
+        },
+    }
+}
+
+#[test]
+fn shapes_match_reference() {
+    GoldenTest::new(200, 80)
+        .check(
+            &mut shapes(),
+            concat!(env!("CARGO_MANIFEST_DIR"), "/tests/golden/shapes.bmp"),
+        )
+        .unwrap();
+}
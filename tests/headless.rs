@@ -0,0 +1,83 @@
+use sdl2::pixels::Color;
+use ugui_r_rs::core::geo::{Point, Rect};
+use ugui_r_rs::fonts::FontRegistry;
+use ugui_r_rs::render::headless::HeadlessRenderer;
+use ugui_r_rs::render::Renderer;
+
+const WHITE: Color = Color::RGBA(255, 255, 255, 255);
+
+fn renderer() -> HeadlessRenderer {
+    HeadlessRenderer::new(8, 8, FontRegistry::new()).unwrap()
+}
+
+/// The drawn pixels, one string per row with `#` for drawn pixels
+fn rows(renderer: &HeadlessRenderer) -> Vec<String> {
+    let framebuffer = renderer.framebuffer();
+    (0..framebuffer.height())
+        .map(|y| {
+            (0..framebuffer.width())
+                .map(|x| {
+                    if framebuffer.pixel(x, y).a > 0 {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn lines_include_both_end_points() {
+    let mut renderer = renderer();
+    renderer.draw_line(Point { x: 1.0, y: 1.0 }, Point { x: 6.0, y: 3.0 }, WHITE);
+    assert_eq!(
+        rows(&renderer),
+        vec![
+            "........", ".##.....", "...##...", ".....##.", "........", "........", "........",
+            "........",
+        ]
+    );
+}
+
+#[test]
+fn lines_are_clipped_to_the_framebuffer() {
+    let mut renderer = renderer();
+    // Lines reaching billions of pixels away only draw their visible part, without stepping through the rest
+    renderer.draw_line(Point { x: -1e9, y: 2.0 }, Point { x: 1e9, y: 2.0 }, WHITE);
+    renderer.draw_line(
+        Point { x: -4.0, y: -4.0 },
+        Point { x: 20.0, y: 20.0 },
+        WHITE,
+    );
+    renderer.draw_line(
+        Point { x: -10.0, y: 5.0 },
+        Point { x: -1.0, y: 20.0 },
+        WHITE,
+    );
+    assert_eq!(
+        rows(&renderer),
+        vec![
+            "#.......", ".#......", "########", "...#....", "....#...", ".....#..", "......#.",
+            ".......#",
+        ]
+    );
+}
+
+#[test]
+fn lines_are_clipped_to_the_clip_area() {
+    let mut renderer = renderer();
+    renderer.push_clip(Rect::new(2.0, 2.0, 4.0, 4.0));
+    renderer.draw_line(Point { x: 0.0, y: 3.0 }, Point { x: 7.0, y: 3.0 }, WHITE);
+    renderer.draw_line(Point { x: 7.0, y: 0.0 }, Point { x: 7.0, y: 7.0 }, WHITE);
+    renderer.pop_clip();
+    renderer.draw_line(Point { x: 0.0, y: 7.0 }, Point { x: 1.0, y: 7.0 }, WHITE);
+    assert_eq!(
+        rows(&renderer),
+        vec![
+            "........", "........", "........", "..####..", "........", "........", "........",
+            "##......",
+        ]
+    );
+}
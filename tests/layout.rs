@@ -0,0 +1,202 @@
+mod common;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use ugui_r_rs::controls::control::{BaseControl, Control, Orientation};
+use ugui_r_rs::controls::image::{ImageData, Stretch};
+use ugui_r_rs::core::geo::{FlowDirection, Point, Rect};
+use ugui_r_rs::fonts::FontKey;
+use ugui_r_rs::render::TextMeasurer;
+use ugui_r_rs::testing::layout::{dump_bounds, layout_snapshot, FixedAdvanceMeasurer};
+use ugui_r_rs::theme::Theme;
+
+fn snapshot(content: &mut Control) -> String {
+    layout_snapshot(
+        content,
+        Rect::new(0.0, 0.0, 640.0, 480.0),
+        &mut FixedAdvanceMeasurer::default(),
+        &Theme::default(),
+    )
+}
+
+#[test]
+fn layout_example() {
+    assert_eq!(
+        snapshot(&mut common::layout_example()),
+        r#"stack (224, 208, 192, 64)
+  stack (224, 208, 48, 64)
+    label "Start" (224, 208, 40, 16)
+    label "Center" (224, 224, 48, 16)
+    label "End" (248, 240, 24, 16)
+    label "Fill" (224, 256, 48, 16)
+  stack (272, 208, 144, 64)
+    label "Start" (272, 208, 40, 16)
+    label "Center" (312, 232, 48, 16)
+    label "End" (360, 256, 24, 16)
+    label "Fill" (384, 208, 32, 64)
+"#
+    );
+}
+
+#[test]
+fn layout_example_right_to_left() {
+    let mut content = common::layout_example();
+    if let Control::Stack { base, .. } = &mut content {
+        base.flow_direction = Some(FlowDirection::RightToLeft);
+    }
+    assert_eq!(
+        snapshot(&mut content),
+        r#"stack (224, 208, 192, 64)
+  stack (368, 208, 48, 64)
+    label "Start" (376, 208, 40, 16)
+    label "Center" (368, 224, 48, 16)
+    label "End" (368, 240, 24, 16)
+    label "Fill" (368, 256, 48, 16)
+  stack (224, 208, 144, 64)
+    label "Start" (328, 208, 40, 16)
+    label "Center" (280, 232, 48, 16)
+    label "End" (256, 256, 24, 16)
+    label "Fill" (224, 208, 32, 64)
+"#
+    );
+}
+
+/// Counts the strings measured, to tell which controls a layout measured
+#[derive(Default)]
+struct CountingMeasurer {
+    inner: FixedAdvanceMeasurer,
+    measured: Rc<RefCell<Vec<String>>>,
+}
+
+impl TextMeasurer for CountingMeasurer {
+    fn has_glyph(&mut self, font: &FontKey, c: char) -> bool {
+        self.inner.has_glyph(font, c)
+    }
+
+    fn font_for(&mut self, font: &FontKey, c: char) -> FontKey {
+        self.inner.font_for(font, c)
+    }
+
+    fn glyph_advance(&mut self, font: &FontKey, c: char) -> f32 {
+        self.inner.glyph_advance(font, c)
+    }
+
+    fn measure(&mut self, font: &FontKey, text: &str) -> Point {
+        self.measured.borrow_mut().push(text.to_string());
+        self.inner.measure(font, text)
+    }
+
+    fn height(&mut self, font: &FontKey) -> f32 {
+        self.inner.height(font)
+    }
+
+    fn ascent(&mut self, font: &FontKey) -> f32 {
+        self.inner.ascent(font)
+    }
+
+    fn line_spacing(&mut self, font: &FontKey) -> f32 {
+        self.inner.line_spacing(font)
+    }
+}
+
+fn layout(content: &mut Control, measurer: &mut dyn TextMeasurer) {
+    content.do_layout(
+        Rect::new(0.0, 0.0, 640.0, 480.0),
+        FlowDirection::default(),
+        measurer,
+        &Theme::default(),
+    );
+}
+
+/// The layout example, with ids on the root, the second stack and the first label
+fn layout_example_with_ids() -> Control {
+    let mut content = common::layout_example();
+    if let Control::Stack { base, .. } = &mut content {
+        base.id = Some("root".to_string());
+        if let Control::Stack { base, .. } = &mut base.children[0] {
+            if let Control::Label { base, .. } = &mut base.children[0] {
+                base.id = Some("start".to_string());
+            }
+        }
+        if let Control::Stack { base, .. } = &mut base.children[1] {
+            base.id = Some("row".to_string());
+        }
+    }
+    content
+}
+
+fn set_start_text(content: &mut Control, value: &str) {
+    content
+        .modify("start", |x| {
+            if let Control::Label { text, .. } = x {
+                *text = value.to_string();
+            }
+        })
+        .unwrap();
+}
+
+#[test]
+fn incremental_layout_matches_full_layout() {
+    let mut content = layout_example_with_ids();
+    layout(&mut content, &mut FixedAdvanceMeasurer::default());
+
+    set_start_text(&mut content, "Starting");
+    content
+        .modify("row", |x| {
+            if let Control::Stack { base, .. } = x {
+                base.flow_direction = Some(FlowDirection::RightToLeft);
+            }
+        })
+        .unwrap();
+    layout(&mut content, &mut FixedAdvanceMeasurer::default());
+    assert!(!content.needs_layout());
+
+    let incremental = dump_bounds(&content);
+    assert_eq!(incremental, snapshot(&mut content.clone()));
+    assert!(incremental.contains(r#"label "Starting" (216, 208, 64, 16)"#));
+}
+
+#[test]
+fn clean_subtrees_are_not_measured_again() {
+    let mut content = layout_example_with_ids();
+    let mut measurer = CountingMeasurer::default();
+    let measured = measurer.measured.clone();
+    layout(&mut content, &mut measurer);
+    assert!(measured.borrow().iter().any(|x| x == "Center"));
+
+    measured.borrow_mut().clear();
+    layout(&mut content, &mut measurer);
+    assert!(measured.borrow().is_empty());
+
+    set_start_text(&mut content, "Starting");
+    layout(&mut content, &mut measurer);
+    assert!(!measured.borrow().is_empty());
+    assert!(measured
+        .borrow()
+        .iter()
+        .all(|x| "Starting".contains(x.as_str())));
+}
+
+#[test]
+fn images_are_measured_with_their_stretch() {
+    let image = |stretch: Stretch| Control::Image {
+        base: BaseControl::default(),
+        source: ImageData::from_rgba(100, 50, vec![0; 100 * 50 * 4]).unwrap(),
+        stretch,
+    };
+    let mut content = Control::Stack {
+        base: BaseControl {
+            children: vec![image(Stretch::None), image(Stretch::Uniform)],
+            ..Default::default()
+        },
+        orientation: Orientation::Vertical,
+    };
+    // The uniformly stretched image grows to the stack's width, keeping its aspect ratio
+    assert_eq!(
+        snapshot(&mut content),
+        r#"stack (0, 0, 640, 370)
+  image (0, 0, 100, 50)
+  image (0, 50, 640, 320)
+"#
+    );
+}
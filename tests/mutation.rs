@@ -0,0 +1,175 @@
+use ugui_r_rs::controls::control::Control::{self, Label, Stack};
+use ugui_r_rs::controls::control::{BaseControl, Orientation};
+use ugui_r_rs::core::geo::{FlowDirection, Rect};
+use ugui_r_rs::testing::driver::TestDriver;
+
+fn label(id: &str, text: &str) -> Control {
+    Label {
+        base: BaseControl {
+            id: Some(id.to_string()),
+            ..Default::default()
+        },
+        text: text.to_string(),
+    }
+}
+
+fn stack(id: &str, children: Vec<Control>) -> Control {
+    Stack {
+        orientation: Orientation::Vertical,
+        base: BaseControl {
+            id: Some(id.to_string()),
+            children,
+            ..Default::default()
+        },
+    }
+}
+
+fn list() -> Control {
+    stack(
+        "root",
+        vec![
+            stack(
+                "items",
+                vec![label("first", "First"), label("second", "Second")],
+            ),
+            stack("footer", vec![label("status", "Ready")]),
+        ],
+    )
+}
+
+fn bounds(driver: &TestDriver, id: &str) -> Rect {
+    driver.find_by_id(id).unwrap().get_base().computed_bounds
+}
+
+fn dirty(driver: &TestDriver, id: &str) -> bool {
+    driver.find_by_id(id).unwrap().get_base().measure_dirty
+}
+
+#[test]
+fn layout_clears_dirty_flags() {
+    let mut content = list();
+    assert!(content.needs_layout());
+
+    let driver = TestDriver::new(content.clone(), 400.0, 300.0);
+    assert!(!driver.content().needs_layout());
+    assert!(driver
+        .content()
+        .find(&|x| x.get_base().measure_dirty)
+        .is_none());
+
+    content.invalidate_layout();
+    assert_eq!(
+        content.find_all(&|x| x.get_base().measure_dirty).len(),
+        content.find_all(&|_| true).len()
+    );
+}
+
+#[test]
+fn modifying_marks_the_control_and_its_ancestors() {
+    let mut driver = TestDriver::new(list(), 400.0, 300.0);
+    driver
+        .content_mut()
+        .modify("first", |x| {
+            if let Label { text, .. } = x {
+                *text = "The first item".to_string();
+            }
+        })
+        .unwrap();
+
+    assert!(dirty(&driver, "first"));
+    assert!(dirty(&driver, "items"));
+    assert!(dirty(&driver, "root"));
+    assert!(!dirty(&driver, "second"));
+    assert!(!dirty(&driver, "footer"));
+    assert!(!dirty(&driver, "status"));
+
+    driver.step();
+    assert!(!driver.content().needs_layout());
+    assert_eq!(bounds(&driver, "first"), Rect::new(0.0, 0.0, 112.0, 16.0));
+}
+
+#[test]
+fn inserted_children_are_laid_out() {
+    let mut driver = TestDriver::new(list(), 400.0, 300.0);
+    assert_eq!(bounds(&driver, "status"), Rect::new(0.0, 32.0, 40.0, 16.0));
+
+    driver
+        .content_mut()
+        .insert_child("items", 1, label("between", "Between"))
+        .unwrap();
+    assert!(driver.content().needs_layout());
+    driver.step();
+
+    assert_eq!(bounds(&driver, "between"), Rect::new(0.0, 16.0, 56.0, 16.0));
+    assert_eq!(bounds(&driver, "second"), Rect::new(0.0, 32.0, 48.0, 16.0));
+    assert_eq!(bounds(&driver, "status"), Rect::new(0.0, 48.0, 40.0, 16.0));
+}
+
+#[test]
+fn inserting_leaves_siblings_clean() {
+    let mut driver = TestDriver::new(list(), 400.0, 300.0);
+    driver
+        .content_mut()
+        .insert_child("items", 1, label("between", "Between"))
+        .unwrap();
+
+    assert!(dirty(&driver, "between"));
+    assert!(dirty(&driver, "items"));
+    assert!(dirty(&driver, "root"));
+    assert!(!dirty(&driver, "first"));
+    assert!(!dirty(&driver, "second"));
+    assert!(!dirty(&driver, "footer"));
+}
+
+#[test]
+fn changing_the_flow_direction_lays_out_descendants() {
+    let mut driver = TestDriver::new(list(), 400.0, 300.0);
+    driver
+        .content_mut()
+        .modify("items", |x| {
+            if let Stack { base, .. } = x {
+                base.flow_direction = Some(FlowDirection::RightToLeft);
+            }
+        })
+        .unwrap();
+    assert!(!dirty(&driver, "first"));
+    driver.step();
+    assert_eq!(bounds(&driver, "first"), Rect::new(8.0, 0.0, 40.0, 16.0));
+    assert_eq!(bounds(&driver, "status"), Rect::new(0.0, 32.0, 40.0, 16.0));
+}
+
+#[test]
+fn removed_and_replaced_children_are_laid_out() {
+    let mut driver = TestDriver::new(list(), 400.0, 300.0);
+
+    let removed = driver.content_mut().remove_child("items", 0).unwrap();
+    assert_eq!(removed.get_base().id.as_deref(), Some("first"));
+    driver.step();
+    assert!(driver.find_by_id("first").is_none());
+    assert_eq!(bounds(&driver, "second"), Rect::new(0.0, 0.0, 48.0, 16.0));
+    assert_eq!(bounds(&driver, "status"), Rect::new(0.0, 16.0, 40.0, 16.0));
+
+    let replaced = driver
+        .content_mut()
+        .replace_child("footer", 0, label("status", "Saving changes"))
+        .unwrap();
+    assert!(matches!(replaced, Label { text, .. } if text == "Ready"));
+    driver.step();
+    assert_eq!(driver.text_of("status").as_deref(), Some("Saving changes"));
+    assert_eq!(bounds(&driver, "status"), Rect::new(0.0, 16.0, 112.0, 16.0));
+}
+
+#[test]
+fn invalid_mutations_are_reported() {
+    let mut content = list();
+    assert!(content.insert_child("missing", 0, label("a", "A")).is_err());
+    assert!(content.insert_child("items", 3, label("a", "A")).is_err());
+    assert!(content.remove_child("footer", 1).is_err());
+    assert!(content.replace_child("footer", 1, label("a", "A")).is_err());
+    assert!(content.modify("missing", |_| {}).is_err());
+
+    content
+        .insert_child("items", 2, label("first", "Again"))
+        .unwrap();
+    assert!(content.modify("first", |_| {}).is_err());
+}
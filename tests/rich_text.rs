@@ -0,0 +1,73 @@
+use sdl2::pixels::Color;
+use ugui_r_rs::controls::rich_text::{parse_markup, TextSpan};
+
+fn texts(spans: &[TextSpan]) -> Vec<&str> {
+    spans.iter().map(|x| x.text.as_str()).collect()
+}
+
+#[test]
+fn parses_nested_tags() {
+    let spans = parse_markup("Plain [b]bold [i]both[/i][/b] [color=#ff000080]red[/color]").unwrap();
+    assert_eq!(texts(&spans), vec!["Plain ", "bold ", "both", " ", "red"]);
+
+    assert!(!spans[0].style.bold);
+    assert!(spans[1].style.bold && !spans[1].style.italic);
+    assert!(spans[2].style.bold && spans[2].style.italic);
+    assert_eq!(spans[3], TextSpan::new(" "));
+    assert_eq!(spans[4].style.color, Some(Color::RGBA(255, 0, 0, 128)));
+}
+
+#[test]
+fn merges_adjacent_spans_with_the_same_style() {
+    let spans = parse_markup("[b]one[/b][b] two[/b]").unwrap();
+    assert_eq!(texts(&spans), vec!["one two"]);
+}
+
+#[test]
+fn parses_links() {
+    let spans = parse_markup("See [link=https://example.com/?a=b]the [b]docs[/b][/link].").unwrap();
+    assert_eq!(texts(&spans), vec!["See ", "the ", "docs", "."]);
+    assert_eq!(spans[0].link, None);
+    assert_eq!(spans[1].link.as_deref(), Some("https://example.com/?a=b"));
+    assert_eq!(spans[2].link.as_deref(), Some("https://example.com/?a=b"));
+    assert!(spans[2].style.bold);
+}
+
+#[test]
+fn unescapes_brackets() {
+    let spans = parse_markup("[[b] is [b]bold[/b]]").unwrap();
+    assert_eq!(texts(&spans), vec!["[b] is ", "bold", "]"]);
+}
+
+#[test]
+fn reports_malformed_markup() {
+    assert_eq!(
+        parse_markup("[b]bold").unwrap_err(),
+        "`[b]` is never closed"
+    );
+    assert_eq!(
+        parse_markup("[b][i]x[/b][/i]").unwrap_err(),
+        "`[/b]` closes `[i]`"
+    );
+    assert_eq!(parse_markup("x[/b]").unwrap_err(), "`[/b]` closes nothing");
+    assert_eq!(
+        parse_markup("[b bold").unwrap_err(),
+        "unterminated tag at `[b bold`"
+    );
+    assert_eq!(
+        parse_markup("[blink]x[/blink]").unwrap_err(),
+        "unknown tag `blink`"
+    );
+    assert_eq!(
+        parse_markup("[link]x[/link]").unwrap_err(),
+        "`link` requires a value"
+    );
+    assert_eq!(
+        parse_markup("[color=red]x[/color]").unwrap_err(),
+        "invalid color `red`"
+    );
+    assert_eq!(
+        parse_markup("[size=big]x[/size]").unwrap_err(),
+        "invalid size `big`"
+    );
+}
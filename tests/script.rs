@@ -0,0 +1,32 @@
+use ugui_r_rs::text::script::{resolve_scripts, script_of, Script};
+
+#[test]
+fn classifies_characters() {
+    assert_eq!(script_of('a'), Script::Latin);
+    assert_eq!(script_of('é'), Script::Latin);
+    assert_eq!(script_of('λ'), Script::Greek);
+    assert_eq!(script_of('ж'), Script::Cyrillic);
+    assert_eq!(script_of('ש'), Script::Hebrew);
+    assert_eq!(script_of('ع'), Script::Arabic);
+    assert_eq!(script_of('中'), Script::Han);
+    assert_eq!(script_of('한'), Script::Hangul);
+    assert_eq!(script_of('ᚠ'), Script::Other);
+
+    // Digits, punctuation, whitespace and marks are shared
+    assert_eq!(script_of('1'), Script::Common);
+    assert_eq!(script_of('٣'), Script::Common);
+    assert_eq!(script_of('،'), Script::Common);
+    assert_eq!(script_of(' '), Script::Common);
+    assert_eq!(script_of('\u{0301}'), Script::Common);
+}
+
+#[test]
+fn common_characters_take_a_neighbouring_script() {
+    use Script::*;
+    assert_eq!(
+        resolve_scripts("12 ab שׁ!"),
+        vec![Latin, Latin, Latin, Latin, Latin, Latin, Hebrew, Hebrew, Hebrew]
+    );
+    assert_eq!(resolve_scripts("1 2"), vec![Common, Common, Common]);
+    assert_eq!(resolve_scripts(""), vec![]);
+}
@@ -0,0 +1,93 @@
+use ugui_r_rs::fonts::TextStyle;
+use ugui_r_rs::testing::layout::FixedAdvanceMeasurer;
+use ugui_r_rs::text::bidi::TextDirection;
+use ugui_r_rs::text::script::Script;
+use ugui_r_rs::text::shaping::{shape_line, ShapedLine};
+use ugui_r_rs::theme::Theme;
+
+// Every character is 8 pixels wide
+fn shape(text: &str, direction: Option<TextDirection>) -> ShapedLine {
+    let font = TextStyle::default().font_key(&Theme::default());
+    shape_line(text, &font, direction, &mut FixedAdvanceMeasurer::default())
+}
+
+fn glyphs(line: &ShapedLine) -> Vec<&str> {
+    line.runs.iter().map(|x| x.glyphs.as_str()).collect()
+}
+
+#[test]
+fn joins_arabic_letters() {
+    // Beh, yeh and teh take their initial, medial and final forms, drawn in visual order
+    let line = shape("بيت", None);
+    assert_eq!(line.direction, TextDirection::RightToLeft);
+    assert_eq!(glyphs(&line), vec!["\u{FE96}\u{FEF4}\u{FE91}"]);
+    assert_eq!(line.runs[0].script, Script::Arabic);
+
+    // Right-joining letters break the word: dal doesn't connect to the following alef
+    let line = shape("دا", None);
+    assert_eq!(glyphs(&line), vec!["\u{FE8D}\u{FEA9}"]);
+
+    // Tatweel connects on both sides
+    let line = shape("بـ", None);
+    assert_eq!(glyphs(&line), vec!["\u{0640}\u{FE91}"]);
+}
+
+#[test]
+fn forms_lam_alef_ligatures() {
+    let line = shape("لا", None);
+    assert_eq!(glyphs(&line), vec!["\u{FEFB}"]);
+    // The ligature covers both characters, splitting its advance between them
+    assert_eq!(line.runs[0].advances, vec![4.0, 4.0]);
+    assert_eq!(line.len, 2);
+
+    // After a joining letter the ligature takes its final form, and the alef doesn't join the following letter
+    let line = shape("سلام", None);
+    assert_eq!(glyphs(&line), vec!["\u{FEE1}\u{FEFC}\u{FEB3}"]);
+
+    // Marks between the lam and alef stay inside the ligature's cluster
+    let line = shape("ل\u{064E}ا", None);
+    assert_eq!(glyphs(&line), vec!["\u{FEFB}\u{064E}"]);
+}
+
+#[test]
+fn orders_runs_visually() {
+    let line = shape("ab אב", Some(TextDirection::LeftToRight));
+    assert_eq!(glyphs(&line), vec!["ab ", "בא"]);
+    assert_eq!(line.runs[1].direction, TextDirection::RightToLeft);
+    assert_eq!(line.runs[1].x, 24.0);
+    assert_eq!(line.width, 40.0);
+
+    let line = shape("אב ab", Some(TextDirection::RightToLeft));
+    assert_eq!(glyphs(&line), vec!["ab", " בא"]);
+}
+
+#[test]
+fn places_carets_across_a_direction_boundary() {
+    // "ab " occupies 0..24 left to right, and "אב" occupies 24..40 right to left
+    let line = shape("ab אב", Some(TextDirection::LeftToRight));
+    let carets: Vec<f32> = (0..=6).map(|i| line.caret_position(i)).collect();
+    assert_eq!(carets, vec![0.0, 8.0, 16.0, 40.0, 32.0, 24.0, 24.0]);
+
+    assert_eq!(line.index_at(-5.0), 0);
+    assert_eq!(line.index_at(10.0), 1);
+    assert_eq!(line.index_at(22.0), 3);
+    assert_eq!(line.index_at(38.0), 3);
+    assert_eq!(line.index_at(30.0), 4);
+    assert_eq!(line.index_at(25.0), 5);
+}
+
+#[test]
+fn selects_discontiguous_ranges_across_a_direction_boundary() {
+    let line = shape("ab אב", Some(TextDirection::LeftToRight));
+    assert_eq!(line.selection_ranges(1, 4), vec![(8.0, 24.0), (32.0, 40.0)]);
+    assert_eq!(line.selection_ranges(0, 5), vec![(0.0, 40.0)]);
+    assert_eq!(line.selection_ranges(3, 3), vec![]);
+}
+
+#[test]
+fn empty_lines_have_no_runs() {
+    let line = shape("", Some(TextDirection::RightToLeft));
+    assert!(line.runs.is_empty());
+    assert_eq!(line.caret_position(0), 0.0);
+    assert_eq!(line.index_at(10.0), 0);
+}
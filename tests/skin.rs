@@ -0,0 +1,73 @@
+use ugui_r_rs::controls::image::ImageData;
+use ugui_r_rs::core::geo::{Rect, Thickness};
+use ugui_r_rs::skin::Skin;
+use ugui_r_rs::theme::VisualState;
+
+fn atlas() -> ImageData {
+    ImageData::from_rgba(32, 16, vec![0; 32 * 16 * 4]).unwrap()
+}
+
+fn parse_error(description: &str) -> String {
+    Skin::parse(atlas(), description).unwrap_err()
+}
+
+#[test]
+fn parses_parts() {
+    let skin = Skin::parse(
+        atlas(),
+        "# style state x y w h left top right bottom\n\
+         \n\
+         button normal 0 0 16 16 4 4 4 4\n\
+         button hover  16 0 16 16 2 3 4 5\n",
+    )
+    .unwrap();
+
+    assert_eq!(skin.parts.len(), 2);
+    let hover = skin.part("button", VisualState::Hover).unwrap();
+    assert_eq!(hover.source, Rect::new(16.0, 0.0, 16.0, 16.0));
+    assert_eq!(hover.margins, Thickness::new(2.0, 3.0, 4.0, 5.0));
+
+    // States without a part use the normal one
+    assert_eq!(
+        skin.part("button", VisualState::Pressed),
+        skin.part("button", VisualState::Normal)
+    );
+    assert!(skin.part("label", VisualState::Normal).is_none());
+}
+
+#[test]
+fn rejects_regions_outside_the_atlas() {
+    assert_eq!(
+        parse_error("button normal 24 0 16 16 0 0 0 0"),
+        "Line 1: region exceeds the atlas"
+    );
+    assert_eq!(
+        parse_error("button normal 0 8 16 16 0 0 0 0"),
+        "Line 1: region exceeds the atlas"
+    );
+    assert_eq!(
+        parse_error("button normal -4 0 16 16 0 0 0 0"),
+        "Line 1: positions, sizes and margins must be finite and non-negative"
+    );
+    assert_eq!(
+        parse_error("button normal 0 0 16 16 8 0 9 0"),
+        "Line 1: margins exceed the region"
+    );
+}
+
+#[test]
+fn rejects_malformed_lines() {
+    assert_eq!(
+        parse_error("# comment\nbutton normal 0 0 16 16 4 4 4"),
+        "Line 2: expected 10 fields, got 9"
+    );
+    assert_eq!(
+        parse_error("button glowing 0 0 16 16 4 4 4 4"),
+        "Line 1: unknown state `glowing`"
+    );
+    assert!(parse_error("button normal 0 0 wide 16 4 4 4 4").starts_with("Line 1: "));
+    assert_eq!(
+        parse_error("button normal 0 0 NaN 16 4 4 4 4"),
+        "Line 1: positions, sizes and margins must be finite and non-negative"
+    );
+}
@@ -0,0 +1,160 @@
+use ugui_r_rs::core::geo::{FlowDirection, Point};
+use ugui_r_rs::fonts::TextStyle;
+use ugui_r_rs::render::TextMeasurer;
+use ugui_r_rs::testing::layout::FixedAdvanceMeasurer;
+use ugui_r_rs::text::{layout_text, TextAlignment, TextLayout, TextTrimming, TextWrapping};
+use ugui_r_rs::theme::Theme;
+
+// Every character is 8 pixels wide and lines are 16 pixels apart
+fn lay_out(
+    text: &str,
+    max_size: Point,
+    wrapping: TextWrapping,
+    trimming: TextTrimming,
+) -> TextLayout {
+    let font = TextStyle::default().font_key(&Theme::default());
+    let mut measurer = FixedAdvanceMeasurer::default();
+    layout_text(text, max_size, wrapping, trimming, 16.0, |x| {
+        measurer.measure(&font, x).x
+    })
+}
+
+fn lines(layout: &TextLayout) -> Vec<&str> {
+    layout.lines.iter().map(|x| x.text.as_str()).collect()
+}
+
+fn width(characters: usize) -> Point {
+    Point {
+        x: characters as f32 * 8.0,
+        y: f32::INFINITY,
+    }
+}
+
+#[test]
+fn wraps_between_words() {
+    let layout = lay_out(
+        "The quick brown fox\njumps",
+        width(10),
+        TextWrapping::Word,
+        TextTrimming::None,
+    );
+    assert_eq!(lines(&layout), vec!["The quick", "brown fox", "jumps"]);
+    assert_eq!(layout.size(), Point { x: 72.0, y: 48.0 });
+}
+
+#[test]
+fn breaks_long_words_between_characters() {
+    let layout = lay_out(
+        "a Supercalifragilistic b",
+        width(8),
+        TextWrapping::Word,
+        TextTrimming::None,
+    );
+    assert_eq!(lines(&layout), vec!["a", "Supercal", "ifragili", "stic b"]);
+}
+
+#[test]
+fn wraps_between_characters() {
+    let layout = lay_out(
+        "abcdefghij\r\nkl",
+        width(3),
+        TextWrapping::Character,
+        TextTrimming::None,
+    );
+    assert_eq!(lines(&layout), vec!["abc", "def", "ghi", "j", "kl"]);
+
+    // Lines narrower than a character still hold one
+    let layout = lay_out(
+        "héllo",
+        Point { x: 4.0, y: 100.0 },
+        TextWrapping::Character,
+        TextTrimming::None,
+    );
+    assert_eq!(lines(&layout), vec!["h", "é", "l", "l", "o"]);
+}
+
+#[test]
+fn long_lines_are_broken_with_few_measurements() {
+    let text = "x".repeat(4000);
+    let mut measured = 0;
+    let layout = layout_text(
+        &text,
+        width(1000),
+        TextWrapping::Character,
+        TextTrimming::None,
+        16.0,
+        |x| {
+            measured += x.len();
+            x.len() as f32 * 8.0
+        },
+    );
+    assert_eq!(layout.lines.len(), 4);
+    assert!(layout.lines.iter().all(|x| x.text.len() == 1000));
+    assert!(measured < 20 * text.len(), "measured {} bytes", measured);
+}
+
+#[test]
+fn trims_with_an_ellipsis() {
+    let layout = lay_out(
+        "Hello world",
+        width(7),
+        TextWrapping::None,
+        TextTrimming::Ellipsis,
+    );
+    // The space before the cut is dropped along with the cut off characters
+    assert_eq!(lines(&layout), vec!["Hello…"]);
+    assert_eq!(layout.lines[0].width, 48.0);
+
+    let layout = lay_out("Hi", width(7), TextWrapping::None, TextTrimming::Ellipsis);
+    assert_eq!(lines(&layout), vec!["Hi"]);
+
+    let layout = lay_out(
+        "Hello",
+        width(0),
+        TextWrapping::None,
+        TextTrimming::Ellipsis,
+    );
+    assert_eq!(lines(&layout), vec!["…"]);
+}
+
+#[test]
+fn trims_the_last_visible_line() {
+    let layout = lay_out(
+        "one two three",
+        Point { x: 40.0, y: 40.0 },
+        TextWrapping::Word,
+        TextTrimming::Ellipsis,
+    );
+    assert_eq!(lines(&layout), vec!["one", "two…"]);
+
+    // The first line is kept even when no line fits
+    let layout = lay_out(
+        "one two three",
+        Point { x: 40.0, y: 8.0 },
+        TextWrapping::Word,
+        TextTrimming::None,
+    );
+    assert_eq!(lines(&layout), vec!["one"]);
+}
+
+#[test]
+fn aligns_lines() {
+    let layout = lay_out("abcde", width(100), TextWrapping::None, TextTrimming::None);
+    let line = &layout.lines[0];
+    assert_eq!(layout.line_offset(line, 100.0, TextAlignment::Start), 0.0);
+    assert_eq!(layout.line_offset(line, 100.0, TextAlignment::Center), 30.0);
+    assert_eq!(layout.line_offset(line, 100.0, TextAlignment::End), 60.0);
+
+    assert_eq!(
+        TextAlignment::Start.resolve(FlowDirection::RightToLeft),
+        TextAlignment::End
+    );
+    assert_eq!(
+        TextAlignment::Center.resolve(FlowDirection::RightToLeft),
+        TextAlignment::Center
+    );
+    assert_eq!(
+        TextAlignment::End.resolve(FlowDirection::LeftToRight),
+        TextAlignment::End
+    );
+}
@@ -0,0 +1,17 @@
+use std::collections::HashMap;
+use ugui_r_rs::theme::{Theme, VisualState};
+
+#[test]
+fn missing_styles_fall_back() {
+    let theme = Theme::dark();
+    assert_eq!(theme.styles("custom"), theme.styles("stack"));
+
+    let empty = Theme {
+        styles: HashMap::new(),
+        ..Theme::dark()
+    };
+    assert_eq!(
+        empty.styles("label").get(VisualState::Hover),
+        Theme::light().styles("stack").get(VisualState::Hover)
+    );
+}
@@ -0,0 +1,115 @@
+use sdl2::keyboard::{Keycode, Mod};
+use ugui_r_rs::controls::control::Control::{self, TreeView};
+use ugui_r_rs::controls::control::{BaseControl, SelectionMode};
+use ugui_r_rs::controls::tree_view::{TreePath, TreeViewItem};
+use ugui_r_rs::core::geo::{FlowDirection, Point};
+use ugui_r_rs::testing::driver::TestDriver;
+
+const ROW_HEIGHT: f32 = 20.0;
+
+fn branch(text: &str, children: &[&str]) -> TreeViewItem {
+    TreeViewItem {
+        expanded: true,
+        children: children.iter().map(|x| TreeViewItem::new(x)).collect(),
+        ..TreeViewItem::new(text)
+    }
+}
+
+fn tree() -> Control {
+    TreeView {
+        base: BaseControl {
+            id: Some("tree".to_string()),
+            ..Default::default()
+        },
+        items: vec![
+            branch("Fruits", &["Apple", "Banana"]),
+            branch("Vegetables", &["Carrot"]),
+        ],
+        selection_mode: SelectionMode::Multiple,
+        row_height: ROW_HEIGHT,
+        state: Default::default(),
+    }
+}
+
+fn selected(driver: &TestDriver) -> Vec<TreePath> {
+    let Some(TreeView { state, .. }) = driver.find_by_id("tree") else {
+        panic!("Expected a tree view");
+    };
+    state.selected.clone()
+}
+
+fn expanded(driver: &TestDriver, index: usize) -> bool {
+    let Some(TreeView { items, .. }) = driver.find_by_id("tree") else {
+        panic!("Expected a tree view");
+    };
+    items[index].expanded
+}
+
+// Rows are clicked on their text, or on the expander column when `x` is 0
+fn click_row(driver: &mut TestDriver, row: usize, x: f32) {
+    let bounds = driver
+        .find_by_id("tree")
+        .unwrap()
+        .get_base()
+        .computed_bounds;
+    driver.click(Point {
+        x: bounds.x + x + 4.0,
+        y: bounds.y + (row as f32 + 0.5) * ROW_HEIGHT,
+    });
+}
+
+#[test]
+fn shift_click_selects_the_visible_range() {
+    let mut driver = TestDriver::new(tree(), 400.0, 300.0);
+    click_row(&mut driver, 4, 40.0);
+    driver.set_modifiers(Mod::LSHIFTMOD);
+    click_row(&mut driver, 1, 40.0);
+    assert_eq!(
+        selected(&driver),
+        vec![vec![0, 0], vec![0, 1], vec![1], vec![1, 0]]
+    );
+
+    // The anchor stays put, so the range shrinks towards it
+    click_row(&mut driver, 3, 40.0);
+    assert_eq!(selected(&driver), vec![vec![1], vec![1, 0]]);
+}
+
+#[test]
+fn collapsing_deselects_hidden_items() {
+    let mut driver = TestDriver::new(tree(), 400.0, 300.0);
+    click_row(&mut driver, 1, 40.0);
+    driver.set_modifiers(Mod::LCTRLMOD);
+    click_row(&mut driver, 4, 40.0);
+    assert_eq!(selected(&driver), vec![vec![0, 0], vec![1, 0]]);
+
+    // Collapsing "Fruits" hides "Apple"
+    click_row(&mut driver, 0, 0.0);
+    assert_eq!(selected(&driver), vec![vec![1, 0]]);
+}
+
+#[test]
+fn arrow_keys_follow_the_flow_direction() {
+    let mut content = tree();
+    if let TreeView { base, .. } = &mut content {
+        base.flow_direction = Some(FlowDirection::RightToLeft);
+    }
+    let mut driver = TestDriver::new(content, 400.0, 300.0);
+    let bounds = driver
+        .find_by_id("tree")
+        .unwrap()
+        .get_base()
+        .computed_bounds;
+
+    // Rows start at the right edge, so "Fruits" is clicked on its text there
+    driver.click(Point {
+        x: bounds.right() - 44.0,
+        y: bounds.y + ROW_HEIGHT / 2.0,
+    });
+    assert_eq!(selected(&driver), vec![vec![0]]);
+
+    // The left arrow points away from the start of the row, so it expands, and the right arrow collapses
+    driver.key_down(Keycode::Right);
+    assert!(!expanded(&driver, 0));
+    driver.key_down(Keycode::Left);
+    assert!(expanded(&driver, 0));
+}